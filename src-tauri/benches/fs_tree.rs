@@ -0,0 +1,43 @@
+//! Builds trees from generated `7z l -slt` listings of 100k and 1M entries.
+//!
+//! Run with `cargo bench --bench fs_tree`. Appending is close to linear if the time per entry
+//! of the 1M listing stays close to the one of the 100k listing, a quadratic lookup would make
+//! it about 10 times slower.
+
+use std::{fmt::Write, hint::black_box, path::Path, time::Instant};
+
+use e_zip_lib::bench::{ListParser, SortOptions};
+
+fn flat(i: usize) -> String {
+    format!("dir/{i:07}.txt")
+}
+
+fn nested(i: usize) -> String {
+    format!("{}/{}/{i:07}.txt", i % 100, i / 100 % 100)
+}
+
+fn main() {
+    for (layout, path) in [("flat", flat as fn(usize) -> String), ("nested", nested)] {
+        for count in [100_000, 1_000_000] {
+            let mut output = String::from("Type = 7z\n\n----------\n");
+            for i in (0..count).rev() {
+                write!(output, "Path = {}\nSize = 1\nAttributes = A\n\n", path(i)).unwrap();
+            }
+
+            let start = Instant::now();
+            let mut parser = ListParser::new(Path::new("bench.7z"), &|_| {});
+            for line in output.lines() {
+                parser.push_line(line).unwrap();
+            }
+            let mut archive = parser.finish();
+            let parsed = start.elapsed();
+            archive.sort(&SortOptions::default());
+            let sorted = start.elapsed() - parsed;
+            println!(
+                "{layout} {count}: parse {parsed:?}, sort {sorted:?}, {:?}/entry",
+                (parsed + sorted) / count as u32
+            );
+            black_box(archive);
+        }
+    }
+}
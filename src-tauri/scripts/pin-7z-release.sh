@@ -0,0 +1,21 @@
+#!/bin/sh
+# Pins a 7-Zip release in 7z-manifest.toml.
+#
+# Downloads the release files of every platform the installer supports and prints their
+# [[release]] entries, newest releases go first in the manifest:
+#
+#   scripts/pin-7z-release.sh 2409 >> 7z-manifest.toml
+set -eu
+
+version="${1:?usage: $0 <version, e.g. 2409>}"
+mirror="${MIRROR:-https://7-zip.org/a}"
+dir="$(mktemp -d)"
+trap 'rm -rf "$dir"' EXIT
+
+# The suffixes of installer::platform_suffix.
+for suffix in extra.7z mac.tar.xz linux-x64.tar.xz linux-arm64.tar.xz; do
+    file="7z${version}-${suffix}"
+    curl -fsSL -o "$dir/$file" "$mirror/$file"
+    sha256="$(sha256sum "$dir/$file" | cut -d ' ' -f 1)"
+    printf '\n[[release]]\nfile = "%s"\nsha256 = "%s"\n' "$file" "$sha256"
+done
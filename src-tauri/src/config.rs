@@ -8,12 +8,18 @@ use std::{
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
+/// Used when the config doesn't set `max_concurrency`.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 2;
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
     target: Target,
     auto_delete: bool, // 自动删除已完成压缩包.
     passwords: Vec<String>,
+    sevenz_path: Option<PathBuf>, // 指定的 7z 可执行文件, 为空时自动检测.
+    sevenz_mirror: Option<String>, // 下载 7-Zip 的镜像地址, 为空时使用官网.
+    max_concurrency: Option<u32>, // 同时解压的压缩包数量, 为空时使用默认值.
 }
 
 impl AppConfig {
@@ -38,6 +44,23 @@ impl AppConfig {
     pub fn passwords(&self) -> Vec<String> {
         self.passwords.clone()
     }
+
+    pub fn sevenz_path(&self) -> Option<&Path> {
+        self.sevenz_path.as_deref()
+    }
+
+    pub fn sevenz_mirror(&self) -> Option<String> {
+        self.sevenz_mirror.clone()
+    }
+
+    pub fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+            .map_or(DEFAULT_MAX_CONCURRENCY, |max| max as usize)
+    }
+
+    pub fn set_sevenz_path(&mut self, sevenz_path: Option<PathBuf>) {
+        self.sevenz_path = sevenz_path;
+    }
 }
 
 // 解压目标.
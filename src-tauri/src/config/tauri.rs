@@ -1,12 +1,16 @@
-use std::{fs, path::PathBuf, sync::Mutex};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
 use tauri::{App, AppHandle, Manager, State};
 
-use crate::config;
+use crate::{config, sevenz::queue};
 
 const CONFIG_FILE_NAME: &str = "config.toml";
 
-fn config_file_path(app_handle: &AppHandle) -> PathBuf {
+pub fn config_file_path(app_handle: &AppHandle) -> PathBuf {
     app_handle
         .path()
         .app_config_dir()
@@ -45,14 +49,17 @@ pub async fn init_config(
 pub async fn update_config(
     app_handle: AppHandle,
     state: State<'_, Mutex<config::AppConfig>>,
-    app_config: config::AppConfig,
+    mut app_config: config::AppConfig,
 ) -> Result<(), String> {
     let mut app_config_state = state.lock().unwrap();
+    // The 7z executable is only changed through `select_7z`.
+    app_config.set_sevenz_path(app_config_state.sevenz_path().map(Path::to_path_buf));
     if *app_config_state != app_config {
         app_config
             .save_to_file(config_file_path(&app_handle))
             .unwrap();
         *app_config_state = app_config.clone();
+        queue::set_max_concurrency(app_config.max_concurrency());
     }
     Ok(())
 }
@@ -1,11 +1,30 @@
 use std::{
-    path::PathBuf,
-    sync::{mpsc, Mutex},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
     thread,
 };
 
 use serde::{Deserialize, Serialize};
-use sevenz::{codepage::OptionalCodepage, error::SevenzError, fs_tree::{ArchiveContents, FsNode}, Archive};
+use sevenz::{
+    codepage::OptionalCodepage,
+    detect::SevenzCandidate,
+    error::SevenzError,
+    fs_tree::{
+        diff::ArchiveDiff,
+        export::ExportFormat,
+        extracted::{CompareBy, ExtractedReport},
+        search::{SearchMatch, SearchQuery},
+        sort::SortOptions,
+        ArchiveContents, FsNode, FsSubtree,
+    },
+    info::SevenZipInfo,
+    installer::{InstallProgress, InstallSource},
+    job::{Job, JobId},
+    session::ListingSessions,
+    unzip::Selection,
+    update::UpdateCheck,
+    Archive,
+};
 use specta::Type;
 use specta_typescript::Typescript;
 use tauri::{ipc::Channel, AppHandle, Manager, State};
@@ -14,18 +33,96 @@ use tauri_specta::{collect_commands, collect_events, Builder, Event};
 mod config;
 mod sevenz;
 
+/// Used by `benches/`, not by the app.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::sevenz::{fs_tree::sort::SortOptions, ListParser};
+}
+
 #[tauri::command]
 #[specta::specta]
-fn check_7z_version(app: AppHandle) -> Result<String, sevenz::error::SevenzError> {
+fn check_7z_version(app: AppHandle) -> Result<SevenZipInfo, sevenz::error::SevenzError> {
     let config_dir = app.path().app_config_dir().unwrap();
     sevenz::check_7z_version(&config_dir)
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn download_7z(app: AppHandle) -> Result<(), sevenz::error::SevenzError> {
+fn list_7z_candidates(app: AppHandle) -> Vec<SevenzCandidate> {
+    let config_dir = app.path().app_config_dir().unwrap();
+    sevenz::detect::candidates(&config_dir)
+}
+
+/// Switches the 7z executable without restarting, `None` goes back to auto-detection.
+#[tauri::command]
+#[specta::specta]
+fn select_7z(
+    app: AppHandle,
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    path: Option<PathBuf>,
+) -> Result<Option<SevenzCandidate>, SevenzError> {
+    let config_dir = app.path().app_config_dir().unwrap();
+    let candidate = sevenz::select_sevenz_command(&config_dir, path.as_deref())?;
+    let mut app_config = app_config.lock().unwrap();
+    app_config.set_sevenz_path(path);
+    app_config.save_to_file(config::tauri::config_file_path(&app))?;
+    Ok(candidate)
+}
+
+/// Installs 7-Zip from the configured mirror, or from `archive` if given.
+#[tauri::command]
+#[specta::specta]
+async fn download_7z(
+    app: AppHandle,
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    archive: Option<PathBuf>,
+    on_progress: Channel<InstallProgress>,
+) -> Result<SevenzCandidate, sevenz::error::SevenzError> {
+    let config_dir = app.path().app_config_dir().unwrap();
+    let source = match archive {
+        Some(archive) => InstallSource::File(archive),
+        None => InstallSource::Mirror(app_config.lock().unwrap().sevenz_mirror()),
+    };
+    sevenz::download_7z(&config_dir, source, |progress| {
+        let _ = on_progress.send(progress);
+    })
+    .await
+}
+
+/// Compares the 7z command in use with the newest release on the configured mirror.
+#[tauri::command]
+#[specta::specta]
+async fn check_7z_update(
+    app: AppHandle,
+    app_config: State<'_, Mutex<config::AppConfig>>,
+) -> Result<UpdateCheck, sevenz::error::SevenzError> {
+    let config_dir = app.path().app_config_dir().unwrap();
+    let installed = sevenz::sevenz_version(&config_dir)?;
+    let mirror = app_config.lock().unwrap().sevenz_mirror();
+    sevenz::update::check_update(&installed, mirror.as_deref()).await
+}
+
+/// Upgrades the private 7z command, the replaced copy is kept as a fallback.
+#[tauri::command]
+#[specta::specta]
+async fn upgrade_7z(
+    app: AppHandle,
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    on_progress: Channel<InstallProgress>,
+) -> Result<SevenzCandidate, sevenz::error::SevenzError> {
     let config_dir = app.path().app_config_dir().unwrap();
-    sevenz::download_7z(&config_dir).await
+    let mirror = app_config.lock().unwrap().sevenz_mirror();
+    sevenz::update::upgrade_private(&config_dir, mirror, |progress| {
+        let _ = on_progress.send(progress);
+    })
+    .await
+}
+
+/// Cancels `download_7z`, the partial download is removed.
+#[tauri::command]
+#[specta::specta]
+fn cancel_download_7z() {
+    sevenz::installer::cancel_install();
 }
 
 #[derive(Serialize, Debug, Clone, Type)]
@@ -44,6 +141,28 @@ impl<T, E> From<Result<T, E>> for SpectaResult<T, E> {
     }
 }
 
+/// Sent while an archive is being listed, with the number of entries listed so far.
+#[derive(Serialize, Debug, Clone, Type, Event)]
+#[serde(rename_all = "camelCase")]
+pub struct ListingProgressEvent {
+    path: PathBuf,
+    #[specta(type = f64)]
+    entries: usize,
+}
+
+impl ListingProgressEvent {
+    /// Returns the `on_entries` callback of [`sevenz::show_archive_content`] for `path`.
+    fn emitter<'a>(app: &'a AppHandle, path: &'a std::path::Path) -> impl Fn(usize) + 'a {
+        move |entries| {
+            let _ = ListingProgressEvent {
+                path: path.to_path_buf(),
+                entries,
+            }
+            .emit(app);
+        }
+    }
+}
+
 #[derive(Serialize, Debug, Clone, Type, Event)]
 pub struct ShowArchiveContentsEvent<'a>(SpectaResult<&'a ArchiveContents, &'a SevenzError>);
 
@@ -55,7 +174,15 @@ impl ShowArchiveContentsEvent<'_> {
         codepage: OptionalCodepage,
         app_config: &config::AppConfig,
     ) -> tauri::Result<()> {
-        let result = sevenz::show_archive_content(archive_path, &password, codepage, app_config);
+        let on_entries = ListingProgressEvent::emitter(&app, &archive_path);
+        let result = sevenz::show_archive_content(
+            &archive_path,
+            &password,
+            codepage,
+            app_config,
+            &SortOptions::default(),
+            &on_entries,
+        );
         ShowArchiveContentsEvent(result.as_ref().into()).emit(&app)
     }
 }
@@ -65,37 +192,158 @@ impl ShowArchiveContentsEvent<'_> {
 async fn show_archives_contents(
     app: AppHandle,
     app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
     paths: Vec<PathBuf>,
     password: String,
+    depth: Option<u32>,
+    sort: Option<SortOptions>,
 ) -> Result<(), ()> {
     let app_config = app_config.lock().unwrap().clone();
+    let sort = sort.unwrap_or_default();
     for path in paths {
-        let result = sevenz::show_archive_content(&path, &password, None, &app_config);
-        ShowArchiveContentsEvent(result.as_ref().into())
+        let on_entries = ListingProgressEvent::emitter(&app, &path);
+        let result =
+            sevenz::show_archive_content(&path, &password, None, &app_config, &sort, &on_entries)
+                .map(|contents| keep_listing(&sessions, contents, depth));
+        ShowArchiveContentsEvent(result.as_deref().into())
             .emit(&app)
             .unwrap();
     }
     Ok(())
 }
 
+/// Keeps the listing for `get_archive_children`, only `depth` levels are sent in the event.
+fn keep_listing(
+    sessions: &ListingSessions,
+    mut contents: ArchiveContents,
+    depth: Option<u32>,
+) -> Arc<ArchiveContents> {
+    contents.set_depth(depth.map(|depth| depth as usize));
+    sessions.insert(contents)
+}
+
 #[tauri::command]
 #[specta::specta]
 async fn refresh_archive_contents(
     app: AppHandle,
     app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
     archive: Archive,
     password: String,
+    depth: Option<u32>,
+    sort: Option<SortOptions>,
 ) -> Result<(), String> {
     let app_config = app_config.lock().unwrap().clone();
 
-    let result =
-        sevenz::show_archive_content(&archive.path, &password, archive.codepage, &app_config);
-    ShowArchiveContentsEvent(result.as_ref().into())
+    let on_entries = ListingProgressEvent::emitter(&app, &archive.path);
+    let result = sevenz::show_archive_content(
+        &archive.path,
+        &password,
+        archive.codepage,
+        &app_config,
+        &sort.unwrap_or_default(),
+        &on_entries,
+    )
+    .map(|contents| keep_listing(&sessions, contents, depth));
+    ShowArchiveContentsEvent(result.as_deref().into())
         .emit(&app)
         .unwrap();
     Ok(())
 }
 
+/// Tests the archive without extracting it, with the passwords of the config if needed.
+#[tauri::command]
+#[specta::specta]
+async fn test_archive(
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    archive: Archive,
+) -> Result<(), SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    sevenz::test_archive(&archive, &app_config)
+}
+
+/// Finds entries in an archive by name or path, see [`SearchQuery`].
+#[tauri::command]
+#[specta::specta]
+async fn search_archive_contents(
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    archive: Archive,
+    query: SearchQuery,
+) -> Result<Vec<SearchMatch>, SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    sessions.get_or_list(&archive, &app_config)?.search(&query)
+}
+
+/// Returns the node at `node_path` of a listed archive, with `depth` levels of children.
+#[tauri::command]
+#[specta::specta]
+async fn get_archive_children(
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    archive: Archive,
+    node_path: String,
+    depth: Option<u32>,
+) -> Result<FsSubtree, SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    let contents = sessions.get_or_list(&archive, &app_config)?;
+    FsSubtree::new(contents, &node_path, depth.map(|depth| depth as usize))
+        .ok_or(SevenzError::EntryNotFound(node_path))
+}
+
+/// Checks which files of the archives are already in the target folder.
+#[tauri::command]
+#[specta::specta]
+async fn check_extracted(
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    archives: Vec<Archive>,
+    target_dir: PathBuf,
+    compare: CompareBy,
+) -> Result<Vec<ExtractedReport>, SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    archives
+        .iter()
+        .map(|archive| {
+            let contents = sessions.get_or_list(archive, &app_config)?;
+            Ok(contents.check_extracted(&target_dir, compare))
+        })
+        .collect()
+}
+
+/// Compares the contents of two archives, usually two versions of the same one.
+#[tauri::command]
+#[specta::specta]
+async fn diff_archives(
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    old: Archive,
+    new: Archive,
+) -> Result<ArchiveDiff, SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    let old = sessions.get_or_list(&old, &app_config)?;
+    let new = sessions.get_or_list(&new, &app_config)?;
+    Ok(old.diff(&new))
+}
+
+/// Writes the listings of the archives to `output`, see [`ExportFormat`].
+#[tauri::command]
+#[specta::specta]
+async fn export_archive_listing(
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    archives: Vec<Archive>,
+    format: ExportFormat,
+    output: PathBuf,
+) -> Result<(), SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    let listings = archives
+        .iter()
+        .map(|archive| sessions.get_or_list(archive, &app_config))
+        .collect::<Result<Vec<_>, _>>()?;
+    sevenz::fs_tree::export::export_listing(&listings, format, &output)
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
 pub struct UnzipedArchiveEvent((PathBuf, sevenz::unzip::UnzipedArchiveStatus));
 
@@ -104,9 +352,11 @@ pub struct UnzipedArchiveEvent((PathBuf, sevenz::unzip::UnzipedArchiveStatus));
 async fn unzip_archives(
     app: AppHandle,
     app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
     archives: Vec<Archive>,
     target_dir: PathBuf,
     global_password: Option<String>,
+    priority: Option<i32>,
 ) -> Result<(), String> {
     let app_config = app_config.lock().unwrap().clone();
     let (tx, rx) = mpsc::channel();
@@ -116,7 +366,21 @@ async fn unzip_archives(
         }
     });
     for archive in archives {
-        sevenz::unzip::unzip(archive, &target_dir, &global_password, &app_config, &tx);
+        // 已列出的压缩包可以得到总大小, 用于计算进度.
+        let selection = sessions
+            .get(&archive.path)
+            .map(|contents| Selection::all(&contents));
+        if let Err(err) = enqueue_unzip(
+            archive,
+            selection,
+            &target_dir,
+            &global_password,
+            &app_config,
+            priority.unwrap_or_default(),
+            &tx,
+        ) {
+            log::warn!("{}", err);
+        }
     }
 
     drop(tx);
@@ -126,6 +390,154 @@ async fn unzip_archives(
     Ok(())
 }
 
+/// Extracts only `paths` of the archive, files or folders picked in its tree.
+#[tauri::command]
+#[specta::specta]
+async fn unzip_selected(
+    app: AppHandle,
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    archive: Archive,
+    paths: Vec<String>,
+    target_dir: PathBuf,
+    global_password: Option<String>,
+) -> Result<(), SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    let contents = sessions.get_or_list(&archive, &app_config)?;
+    let selection = Selection::new(&contents, paths)?;
+    unzip_selection(
+        app,
+        archive,
+        selection,
+        target_dir,
+        global_password,
+        app_config,
+    )
+}
+
+/// Extracts the folder or file at `root` of the archive straight into `target_dir`,
+/// without the folders containing it.
+///
+/// The paths of [`sevenz::unzip::UnzipedArchiveStatus::Ok`] are relative to `target_dir`.
+#[tauri::command]
+#[specta::specta]
+async fn unzip_subtree(
+    app: AppHandle,
+    app_config: State<'_, Mutex<config::AppConfig>>,
+    sessions: State<'_, ListingSessions>,
+    archive: Archive,
+    root: String,
+    target_dir: PathBuf,
+    global_password: Option<String>,
+) -> Result<(), SevenzError> {
+    let app_config = app_config.lock().unwrap().clone();
+    let contents = sessions.get_or_list(&archive, &app_config)?;
+    let selection = Selection::subtree(&contents, root)?;
+    unzip_selection(
+        app,
+        archive,
+        selection,
+        target_dir,
+        global_password,
+        app_config,
+    )
+}
+
+fn unzip_selection(
+    app: AppHandle,
+    archive: Archive,
+    selection: Selection,
+    target_dir: PathBuf,
+    global_password: Option<String>,
+    app_config: config::AppConfig,
+) -> Result<(), SevenzError> {
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        for res in rx {
+            UnzipedArchiveEvent(res).emit(&app).unwrap();
+        }
+    });
+    let result = enqueue_unzip(
+        archive,
+        Some(selection),
+        &target_dir,
+        &global_password,
+        &app_config,
+        0,
+        &tx,
+    );
+
+    drop(tx);
+
+    handle.join().unwrap();
+    result
+}
+
+/// Queues the extraction, the events are sent until the extraction ends.
+fn enqueue_unzip(
+    archive: Archive,
+    selection: Option<Selection>,
+    target_dir: &Path,
+    global_password: &Option<String>,
+    app_config: &config::AppConfig,
+    priority: i32,
+    tx: &mpsc::Sender<(PathBuf, sevenz::unzip::UnzipedArchiveStatus)>,
+) -> Result<(), SevenzError> {
+    let (target_dir, global_password, app_config) = (
+        target_dir.to_path_buf(),
+        global_password.clone(),
+        app_config.clone(),
+    );
+    let sender = tx.clone();
+    // 排队时就有 ID, 可以取消还在等待的解压.
+    let job = Job::register(archive.path.clone(), tx);
+    let output_dir = sevenz::unzip::output_dir(&archive.path, &target_dir, selection.as_ref());
+    sevenz::queue::enqueue(
+        archive.path.clone(),
+        output_dir,
+        job.id(),
+        priority,
+        tx,
+        move || {
+            sevenz::unzip::unzip_selected(
+                archive,
+                selection.as_ref(),
+                &target_dir,
+                &global_password,
+                &app_config,
+                &job,
+                &sender,
+            )
+        },
+    )
+}
+
+/// Changes the priority of an archive waiting in the extraction queue.
+#[tauri::command]
+#[specta::specta]
+fn set_unzip_priority(archive_path: PathBuf, priority: i32) -> Result<(), SevenzError> {
+    sevenz::queue::set_priority(&archive_path, priority)
+}
+
+/// Cancels the extraction, see [`sevenz::job`].
+#[tauri::command]
+#[specta::specta]
+fn cancel_job(id: JobId) -> Result<(), SevenzError> {
+    sevenz::job::cancel(id)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn pause_job(id: JobId) -> Result<(), SevenzError> {
+    sevenz::job::pause(id)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn resume_job(id: JobId) -> Result<(), SevenzError> {
+    sevenz::job::resume(id)
+}
+
 #[derive(Debug, Clone, Serialize, Type)]
 pub struct DeletedArchiveEvent((PathBuf, Option<String>));
 
@@ -157,17 +569,35 @@ pub fn run() {
         // Then register them (separated by a comma)
         .commands(collect_commands![
             check_7z_version,
+            list_7z_candidates,
+            select_7z,
             download_7z,
+            cancel_download_7z,
+            check_7z_update,
+            upgrade_7z,
             unzip_archives,
+            unzip_selected,
+            unzip_subtree,
+            cancel_job,
+            pause_job,
+            resume_job,
+            set_unzip_priority,
             delete_archives,
             show_archives_contents,
             refresh_archive_contents,
+            test_archive,
+            search_archive_contents,
+            get_archive_children,
+            export_archive_listing,
+            diff_archives,
+            check_extracted,
             config::tauri::init_config,
             config::tauri::update_config,
         ])
         .events(collect_events![
             UnzipedArchiveEvent,
-            ShowArchiveContentsEvent
+            ShowArchiveContentsEvent,
+            ListingProgressEvent
         ])
         .typ::<FsNode>()
         .typ::<ArchiveContents>();
@@ -187,6 +617,12 @@ pub fn run() {
         .setup(move |app| {
             builder.mount_events(app);
             config::tauri::setup_handler(app).unwrap();
+            let config_dir = app.path().app_config_dir().unwrap();
+            let app_config = app.state::<Mutex<config::AppConfig>>();
+            let sevenz_path = app_config.lock().unwrap().sevenz_path().map(PathBuf::from);
+            sevenz::init_sevenz_command(&config_dir, sevenz_path.as_deref());
+            sevenz::queue::set_max_concurrency(app_config.lock().unwrap().max_concurrency());
+            app.manage(ListingSessions::default());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -1,9 +1,8 @@
 use std::{
     collections::HashMap,
-    fs, io,
     num::NonZeroUsize,
     path::{Path, PathBuf},
-    process::{Command, Output},
+    process::Command,
     sync::{LazyLock, Mutex, MutexGuard, OnceLock},
 };
 
@@ -12,39 +11,75 @@ use codepage::{
     OptionalCodepage,
 };
 use lru::LruCache;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use strum::{Display, EnumIter, IntoEnumIterator, VariantNames};
-use tauri_plugin_http::reqwest;
 use time::{format_description::well_known::Iso8601, OffsetDateTime, PrimitiveDateTime};
 use time_tz::{system::get_timezone, PrimitiveDateTimeExt};
 
 use crate::config;
+use backend::{archive_backend, sevenz_cli::SevenzCli, ArchiveBackend};
+use detect::SevenzCandidate;
+use entry::ArchiveEntry;
 use error::SevenzError;
-use fs_tree::ArchiveContents;
+use fs_tree::{sort::SortOptions, ArchiveContents, ArchiveInfo};
+use info::SevenZipInfo;
+use installer::{InstallProgress, InstallSource};
 use multi_volume::{archive_multi_volume, get_first_volume};
 
+pub mod backend;
 pub mod codepage;
 pub mod delete;
+pub mod detect;
+pub mod entry;
 pub mod error;
 pub mod fs_tree;
+pub mod info;
+pub mod installer;
+pub mod job;
 pub mod multi_volume;
+pub mod queue;
+pub mod session;
 pub mod unzip;
+pub mod update;
 
-static SEVENZ_COMMAND: LazyLock<Mutex<Option<String>>> = LazyLock::new(|| {
-    let command = if cfg!(windows) {
-        "7z".to_string() // Windows 路径
-    } else if cfg!(unix) {
-        "7zz".to_string() // Linux, macOS 路径
-    } else {
-        panic!("Unsupported operating system");
+/// The 7z command used by [`SevenzCli`], set by [`select_sevenz_command`].
+static SEVENZ_COMMAND: Mutex<Option<String>> = Mutex::new(None);
+
+/// Selects the 7z command used from now on.
+///
+/// With `path`, the executable is checked and used. Without it, the best executable found by
+/// [`detect::candidates`] is used, or none if 7-Zip is not installed.
+///
+/// # Errors
+///
+/// Returns `Err(SevenzError::Invalid7z)` if `path` is not a working 7z executable.
+pub fn select_sevenz_command(
+    config_dir: &Path,
+    path: Option<&Path>,
+) -> Result<Option<SevenzCandidate>, SevenzError> {
+    let candidate = match path {
+        Some(path) => Some(
+            SevenzCandidate::probe(path)
+                .ok_or_else(|| SevenzError::Invalid7z(path.to_path_buf()))?,
+        ),
+        None => detect::candidates(config_dir).into_iter().next(),
     };
-    Mutex::new(match Command::new(&command).output() {
-        Ok(_) => Some(command),
-        Err(_) => None,
-    })
-});
+    *SEVENZ_COMMAND.lock().unwrap() = candidate
+        .as_ref()
+        .map(|candidate| candidate.path.to_string_lossy().into_owned());
+    Ok(candidate)
+}
+
+/// Selects the 7z command at startup.
+///
+/// Falls back to auto-detection if the configured executable doesn't work anymore.
+pub fn init_sevenz_command(config_dir: &Path, configured: Option<&Path>) {
+    if let Err(err) = select_sevenz_command(config_dir, configured) {
+        log::warn!("{}, 改为自动检测", err);
+        let _ = select_sevenz_command(config_dir, None);
+    }
+}
 
 /// Returns the local path of the 7z command.
 ///
@@ -67,15 +102,7 @@ static SEVENZ_COMMAND: LazyLock<Mutex<Option<String>>> = LazyLock::new(|| {
 fn sevenz_command_local(config_dir: &Path) -> Option<String> {
     let mut command = SEVENZ_COMMAND.lock().unwrap();
     if (*command).is_none() {
-        let path = if cfg!(windows) {
-            config_dir.join("7z-extra").join("7za.exe")
-        } else if cfg!(target_os = "macos") {
-            config_dir.join("7z-macos").join("7zz")
-        } else if cfg!(target_os = "linux") {
-            config_dir.join("7z-linux").join("7zz")
-        } else {
-            panic!("Unsupported operating system");
-        };
+        let path = detect::private_command_path(config_dir);
         if Command::new(&path).output().is_ok() {
             *command = Some(path.to_str().unwrap().to_string())
         }
@@ -83,23 +110,6 @@ fn sevenz_command_local(config_dir: &Path) -> Option<String> {
     command.clone()
 }
 
-/// Returns a `Command` instance with the path of the 7z command.
-///
-/// If the 7z command is not found in the system path, it will search for the 7z command in the
-/// `config_dir` directory. If the 7z command is found, it will be stored in the `SEVENZ_COMMAND`
-/// global variable.
-///
-/// # Errors
-///
-/// If the 7z command is not found in the system path or in the `config_dir` directory, it will
-/// return `Err(SevenzError::NotFound7z)`.
-pub fn sevenz_command() -> Result<Command, SevenzError> {
-    let Some(sevenz_command) = SEVENZ_COMMAND.lock().unwrap().clone() else {
-        return Err(SevenzError::NotFound7z);
-    };
-    Ok(Command::new(sevenz_command))
-}
-
 static MAP: OnceLock<Mutex<HashMap<PathBuf, bool>>> = OnceLock::new();
 /// 保存压缩文件是否有根文件夹.
 /// 如果没有根文件夹, 会在解压时自动创建根文件夹.
@@ -112,11 +122,12 @@ pub fn archives_have_root_dir() -> MutexGuard<'static, HashMap<PathBuf, bool>> {
 
 type FilesModified = HashMap<String, Option<OffsetDateTime>>;
 
+/// Modified times by entry path of the archives listed last, the lock is taken once per listing.
 static FILES_MODIFIED: LazyLock<Mutex<LruCache<PathBuf, FilesModified>>> =
     LazyLock::new(|| Mutex::new(LruCache::new(NonZeroUsize::new(1_000).unwrap())));
 
-// 验证 7zip 命令行工具是否安装.
-pub fn check_7z_version(config_dir: &Path) -> Result<String, SevenzError> {
+/// Returns the 7z command in use, or the private one in `config_dir`.
+fn sevenz_cli(config_dir: &Path) -> Result<SevenzCli, SevenzError> {
     let binding = SEVENZ_COMMAND.lock().unwrap().clone();
     let sevenz_command = match binding {
         Some(sevenz_command) => sevenz_command,
@@ -125,92 +136,32 @@ pub fn check_7z_version(config_dir: &Path) -> Result<String, SevenzError> {
             None => return Err(SevenzError::NotFound7z),
         },
     };
-
-    match Command::new(sevenz_command).arg("-h").output() {
-        Ok(output) => {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                // 解析版本信息，7-Zip 的帮助信息中包含版本字符串
-                if let Some(version_line) =
-                    output_str.lines().find(|line| line.starts_with("7-Zip"))
-                {
-                    Ok(format!("7-Zip 已安装: {}", version_line))
-                } else {
-                    Err(SevenzError::CommandError(
-                        "7-Zip 已安装，但版本信息解析失败。".to_string(),
-                    ))
-                }
-            } else {
-                Err(SevenzError::NotFound7z)
-            }
-        }
-        Err(err) => Err(err.into()),
-    }
+    Ok(SevenzCli::with_command(sevenz_command))
 }
 
-pub async fn download_7z(config_dir: &Path) -> Result<(), SevenzError> {
-    let res = reqwest::get("https://7-zip.org/download.html")
-        .await?
-        .text()
-        .await?;
-    let suffix = if cfg!(windows) {
-        "extra.7z"
-    } else if cfg!(target_os = "macos") {
-        "mac.tar.xz"
-    } else if cfg!(target_os = "linux") {
-        "linux-x64.tar.xz"
-    } else {
-        panic!("Unsupported operating system");
-    };
-    let re = Regex::new(&format!(r#"href="a/(7z[0-9.]+-{suffix})""#)).unwrap();
-    if let Some(link) = re.captures(&res) {
-        if let Some(filename) = link.get(1) {
-            let downlowd_link = format!("https://7-zip.org/a/{}", filename.as_str());
-            let mut res = reqwest::get(&downlowd_link).await?;
-            if !config_dir.exists() {
-                fs::create_dir(config_dir)?;
-            }
-            let file_path = config_dir.join(filename.as_str());
-            {
-                let mut dest = fs::File::create(&file_path)?;
-                while let Some(chunk) = res.chunk().await? {
-                    io::copy(&mut chunk.as_ref(), &mut dest)?;
-                }
-            }
+// 验证 7zip 命令行工具是否安装.
+pub fn check_7z_version(config_dir: &Path) -> Result<SevenZipInfo, SevenzError> {
+    sevenz_cli(config_dir)?.info()
+}
 
-            let dir_name = if cfg!(windows) {
-                "7z-extra"
-            } else if cfg!(target_os = "macos") {
-                "7z-macos"
-            } else if cfg!(target_os = "linux") {
-                "7z-linux"
-            } else {
-                "7z-unknown"
-            };
-
-            let target_dir = config_dir.join(dir_name);
-            if !target_dir.exists() {
-                fs::create_dir(&target_dir)?;
-            }
+/// Returns the version of the 7z command in use, see [`ArchiveBackend::version`].
+pub fn sevenz_version(config_dir: &Path) -> Result<String, SevenzError> {
+    sevenz_cli(config_dir)?.version()
+}
 
-            let output = Command::new("tar")
-                .arg("-xvf")
-                .arg(&file_path)
-                .arg("-C")
-                .arg(config_dir.join(dir_name))
-                .output()?;
-            fs::remove_file(&file_path)?;
-            if output.status.success() {
-                sevenz_command_local(config_dir);
-            } else {
-                return Err(SevenzError::CommandError(format!(
-                    "解压 7-Zip 失败: {}",
-                    String::from_utf8_lossy(&output.stderr)
-                )));
-            }
-        }
-    }
-    Ok(())
+/// Installs the private 7z command and selects it if no 7z command is in use.
+pub async fn download_7z<F>(
+    config_dir: &Path,
+    source: InstallSource,
+    on_progress: F,
+) -> Result<SevenzCandidate, SevenzError>
+where
+    F: Fn(InstallProgress) + Send + Sync,
+{
+    let candidate = installer::install_7z(config_dir, source, on_progress).await?;
+    SevenZipInfo::forget(&candidate.path.to_string_lossy());
+    sevenz_command_local(config_dir);
+    Ok(candidate)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Type)]
@@ -226,6 +177,34 @@ pub fn show_archive_content<P: AsRef<Path>>(
     password: &str,
     codepage: OptionalCodepage,
     app_config: &config::AppConfig,
+    sort: &SortOptions,
+    on_entries: &dyn Fn(usize),
+) -> Result<ArchiveContents, SevenzError> {
+    let backend = archive_backend(archive_path.as_ref())?;
+    show_archive_content_with(
+        backend.as_ref(),
+        archive_path,
+        password,
+        codepage,
+        app_config,
+        sort,
+        on_entries,
+    )
+}
+
+/// Lists the archive with the given backend.
+///
+/// Retries with the passwords of `app_config` and with every [`Codepage`] until the listing
+/// succeeds. `on_entries` is called with the number of entries listed so far, see
+/// [`ListParser`].
+pub fn show_archive_content_with<P: AsRef<Path>>(
+    backend: &dyn ArchiveBackend,
+    archive_path: P,
+    password: &str,
+    codepage: OptionalCodepage,
+    app_config: &config::AppConfig,
+    sort: &SortOptions,
+    on_entries: &dyn Fn(usize),
 ) -> Result<ArchiveContents, SevenzError> {
     let mut file_path = archive_path.as_ref().to_path_buf();
     // 判断文件是否是文件夹.
@@ -246,7 +225,14 @@ pub fn show_archive_content<P: AsRef<Path>>(
     };
 
     let mut current_password = password.to_owned();
-    let mut result = sevenz_list_command(&file_path, &current_password, codepage.clone());
+    let mut result = list_archive(
+        backend,
+        &file_path,
+        &current_password,
+        codepage.clone(),
+        sort,
+        on_entries,
+    );
     if let Ok(result) = result {
         return wrap_result(result);
     }
@@ -261,12 +247,26 @@ pub fn show_archive_content<P: AsRef<Path>>(
                 SevenzError::NeedPassword(_) if passwords.peek().is_some() => {
                     let password = passwords.next().unwrap();
                     current_password = password;
-                    result = sevenz_list_command(&file_path, &current_password, codepage.clone());
+                    result = list_archive(
+                        backend,
+                        &file_path,
+                        &current_password,
+                        codepage.clone(),
+                        sort,
+                        on_entries,
+                    );
                 }
                 SevenzError::InvalidUtf8(ref s) if codepages.peek().is_some() => {
                     println!("error string: {}", s);
                     let codepage = codepages.next().unwrap();
-                    result = sevenz_list_command(&file_path, &current_password, codepage.into());
+                    result = list_archive(
+                        backend,
+                        &file_path,
+                        &current_password,
+                        codepage.into(),
+                        sort,
+                        on_entries,
+                    );
                 }
                 _ => return Err(e),
             },
@@ -274,46 +274,97 @@ pub fn show_archive_content<P: AsRef<Path>>(
     }
 }
 
-const LIST_COMMAND_ARGS: [&str; 3] = ["l", "-slt", "-sccUTF-8"];
+/// Tests the archive without extracting it, see [`ArchiveBackend::test`].
+pub fn test_archive(archive: &Archive, app_config: &config::AppConfig) -> Result<(), SevenzError> {
+    let backend = archive_backend(&archive.path)?;
+    test_archive_with(backend.as_ref(), archive, app_config)
+}
+
+/// Tests the archive with the given backend.
+///
+/// Retries with the passwords of `app_config` while the password is missing or wrong.
+pub fn test_archive_with(
+    backend: &dyn ArchiveBackend,
+    archive: &Archive,
+    app_config: &config::AppConfig,
+) -> Result<(), SevenzError> {
+    let mut result = backend.test(archive, archive.password.as_deref());
+    for password in app_config.passwords() {
+        match result {
+            Err(SevenzError::NeedPassword(_)) => result = backend.test(archive, Some(&password)),
+            _ => break,
+        }
+    }
+    result
+}
 
 pub struct OutputFile {
     path: String,
+    /// The path as stored in the archive, `None` if only the decoded path is known, like with 7z.
+    raw_path: Option<Vec<u8>>,
     is_dir: bool,
     modified: Option<OffsetDateTime>,
-}
-
-impl OutputFile {
-    pub fn cache_modified(&self, archive_path: PathBuf) {
-        let mut binding = FILES_MODIFIED.lock().unwrap();
-        let map = binding.get_or_insert_mut(archive_path, FilesModified::new);
-        map.insert(self.path.clone(), self.modified);
-    }
+    entry: ArchiveEntry,
 }
 
 #[derive(Display, EnumIter)]
 enum Prefix {
     Path,
     Folder,
+    Size,
+    #[strum(serialize = "Packed Size")]
+    PackedSize,
     Attributes,
     Modified,
+    Created,
+    Accessed,
+    #[strum(serialize = "CRC")]
+    Crc,
+    Method,
+    Encrypted,
+    Comment,
+    Block,
     Volumes,
+    Type,
+    #[strum(serialize = "Physical Size")]
+    PhysicalSize,
+    #[strum(serialize = "Headers Size")]
+    HeadersSize,
+    Solid,
+    Blocks,
+    Characteristics,
 }
 
 #[derive(Display, VariantNames)]
 enum LineType {
     Path(String),
     Folder(bool),
-    Attributes(bool),
+    Size(u64),
+    PackedSize(u64),
+    Attributes(String),
     Modified(OffsetDateTime),
+    Created(OffsetDateTime),
+    Accessed(OffsetDateTime),
+    Crc(String),
+    Method(String),
+    Encrypted(bool),
+    Comment(String),
+    Block(u64),
     Volumes(usize),
+    Type(String),
+    PhysicalSize(u64),
+    HeadersSize(u64),
+    Solid(bool),
+    Blocks(u64),
+    Characteristics(String),
 }
 
 impl LineType {
     /// Creates a new `LineType` based on the line's prefix.
     ///
     /// This function iterates over the defined prefixes and checks if the given line
-    /// starts with any of them. If a match is found, it returns the corresponding
-    /// `LineType` by invoking the `_new` function with the matched prefix and line.
+    /// starts with any of them followed by ` = `. If a match is found, it returns the
+    /// corresponding `LineType` with the parsed value.
     ///
     /// # Arguments
     ///
@@ -321,180 +372,248 @@ impl LineType {
     ///
     /// # Returns
     ///
-    /// * `Option<LineType>` - Returns `Some(LineType)` if a prefix match is found,
-    ///   otherwise returns `None`.
+    /// * `Option<LineType>` - Returns `Some(LineType)` if a prefix match is found and the
+    ///   value is not empty, otherwise returns `None`.
     fn new(line: &str) -> Option<LineType> {
         Prefix::iter().find_map(|prefix| {
-            let prefix_s = prefix.to_string();
-            if !line.starts_with(&prefix_s) {
+            let prefix_full = format!("{} = ", prefix);
+            let value = line.strip_prefix(&prefix_full)?.to_string();
+            if value.is_empty() && !matches!(prefix, Prefix::Path) {
                 return None;
             }
-
-            let prefix_full = format!("{} = ", prefix_s);
-            let value = line.replace(&prefix_full, "");
             Some(match prefix {
                 Prefix::Path => LineType::Path(value),
                 Prefix::Folder => LineType::Folder(value == "+"),
-                Prefix::Attributes => LineType::Attributes(value.starts_with("D")),
-                Prefix::Modified => {
-                    let date = value.replace(" ", "T");
-                    // date.push('Z'); PrimitiveDateTime not need timezone, but OffsetDateTime need.
-                    let datetime = PrimitiveDateTime::parse(&date, &Iso8601::DEFAULT).unwrap();
-                    let offset_datetime = datetime.assume_timezone_utc(get_timezone().unwrap());
-                    LineType::Modified(offset_datetime)
-                }
-                Prefix::Volumes => LineType::Volumes(value.parse().unwrap()),
+                Prefix::Size => LineType::Size(value.parse().ok()?),
+                Prefix::PackedSize => LineType::PackedSize(value.parse().ok()?),
+                Prefix::Attributes => LineType::Attributes(value),
+                Prefix::Modified => LineType::Modified(parse_datetime(&value)?),
+                Prefix::Created => LineType::Created(parse_datetime(&value)?),
+                Prefix::Accessed => LineType::Accessed(parse_datetime(&value)?),
+                Prefix::Crc => LineType::Crc(value),
+                Prefix::Method => LineType::Method(value),
+                Prefix::Encrypted => LineType::Encrypted(value == "+"),
+                Prefix::Comment => LineType::Comment(value),
+                Prefix::Block => LineType::Block(value.parse().ok()?),
+                Prefix::Volumes => LineType::Volumes(value.parse().ok()?),
+                Prefix::Type => LineType::Type(value),
+                Prefix::PhysicalSize => LineType::PhysicalSize(value.parse().ok()?),
+                Prefix::HeadersSize => LineType::HeadersSize(value.parse().ok()?),
+                Prefix::Solid => LineType::Solid(value == "+"),
+                Prefix::Blocks => LineType::Blocks(value.parse().ok()?),
+                Prefix::Characteristics => LineType::Characteristics(value),
             })
         })
     }
 }
 
-fn sevenz_list_command<P: AsRef<Path>>(
-    archive_path: P,
+/// Parses a 7z time like `2024-01-02 03:04:05` or `2024-01-02 03:04:05.1234567`, in local time.
+fn parse_datetime(value: &str) -> Option<OffsetDateTime> {
+    let date = value.replace(" ", "T");
+    // date.push('Z'); PrimitiveDateTime not need timezone, but OffsetDateTime need.
+    let datetime = PrimitiveDateTime::parse(&date, &Iso8601::DEFAULT).ok()?;
+    Some(datetime.assume_timezone_utc(get_timezone().ok()?))
+}
+
+fn list_archive(
+    backend: &dyn ArchiveBackend,
+    archive_path: &Path,
     password: &str,
     codepage: OptionalCodepage,
-) -> Result<fs_tree::ArchiveContents, SevenzError> {
-    let output = sevenz_list_command_output(&archive_path, password, codepage.clone());
-    match output {
-        Ok(s) => {
-            let mut archive = ArchiveContents::new(archive_path.as_ref().to_path_buf());
-
-            let mut lines = s.lines();
-            lines
-                .by_ref()
-                .take_while(|line| *line != "----------")
-                .for_each(|line| {
-                    let line_type = LineType::new(line);
-                    if let Some(LineType::Volumes(volumes @ 2..)) = line_type {
-                        let multi_volume = archive_multi_volume(&archive_path, volumes);
-                        archive.set_multi_volume(multi_volume);
-                    }
-                });
-
-            let groups = lines
-                // TODO: take_while skip_while 是否包含 "----------"?
-                // .skip_while(|line| *line != "----------")
-                // .skip(1) // 跳过 "----------".
-                .fold(vec![vec![]], |mut groups, line| {
-                    if line.is_empty() {
-                        groups.push(vec![]);
-                    } else {
-                        groups.last_mut().unwrap().push(line);
-                    }
-                    groups
-                });
-            let output_files = groups.iter().map(|group| {
-                let mut path: String = "".to_string();
-                let mut is_dir: bool = false;
-                let mut modified: Option<OffsetDateTime> = None;
-                for line in group {
-                    match LineType::new(line) {
-                        Some(LineType::Path(s)) => {
-                            let check = s
-                                .chars()
-                                .filter(|c| {
-                                    is_replacement_character(*c)
-                                        || is_halfwidth_katakana(*c)
-                                        || is_latin_capital_letter(*c)
-                                })
-                                .count();
-                            let chars_count = s.chars().count();
-                            if check as f64 / chars_count as f64 > 0.5 {
-                                return Err(SevenzError::InvalidUtf8(s.to_owned()));
-                            };
-                            path = s;
-                        }
-                        Some(LineType::Folder(b) | LineType::Attributes(b)) => {
-                            is_dir |= b;
-                        }
-                        Some(LineType::Modified(datetime)) => {
-                            modified = Some(datetime);
-                        }
-                        Some(LineType::Volumes(_)) | None => {}
-                    };
-                }
-                Ok(OutputFile {
-                    path,
-                    is_dir,
-                    modified,
-                })
-            });
+    sort: &SortOptions,
+    on_entries: &dyn Fn(usize),
+) -> Result<ArchiveContents, SevenzError> {
+    let mut archive = backend.list(archive_path, password, codepage.clone(), on_entries)?;
+    let modified = archive.take_modified();
+    FILES_MODIFIED
+        .lock()
+        .unwrap()
+        .put(archive_path.to_path_buf(), modified);
+    archive.sort(sort);
+    archive.set_password(password);
+    archive.set_codepage(codepage);
+    archive.set_has_root_dir();
+    Ok(archive)
+}
 
-            output_files.rev().try_fold(
-                // NOTE: try_fold
-                &mut archive,
-                |tree, file| match file {
-                    Ok(file) => {
-                        file.cache_modified(archive_path.as_ref().to_path_buf());
-                        tree.append_file(file);
-                        Ok(tree)
-                    }
-                    Err(e) => Err(e),
-                },
-            )?;
-            archive.sort();
-            archive.set_password(password);
-            archive.set_codepage(codepage);
-            archive.set_has_root_dir();
-
-            Ok(archive)
-        }
-        Err(e) => Err(e),
+/// Parses the complete output of `7z l -slt` into the archive contents.
+#[cfg(test)]
+fn parse_list_output<P: AsRef<Path>>(
+    archive_path: P,
+    output: &str,
+) -> Result<ArchiveContents, SevenzError> {
+    let mut parser = ListParser::new(archive_path.as_ref(), &|_| {});
+    for line in output.lines() {
+        parser.push_line(line)?;
     }
+    Ok(parser.finish())
 }
 
-fn sevenz_list_command_output<P: AsRef<Path>>(
-    archive_path: P,
-    password: &str,
-    codepage: OptionalCodepage,
-) -> Result<String, SevenzError> {
-    let mut command = sevenz_command()?;
-    command.args(LIST_COMMAND_ARGS);
-    if !password.is_empty() {
-        command.set_password(password);
+/// Number of entries between two calls of the `on_entries` callback while listing.
+const LIST_PROGRESS_STEP: usize = 1_000;
+
+/// Parses the output of `7z l -slt` line by line, while 7z is still listing.
+///
+/// The tree is built as the entries come in, and `on_entries` is called with the number of
+/// entries so far every [`LIST_PROGRESS_STEP`] entries.
+pub struct ListParser<'a> {
+    archive: ArchiveContents,
+    archive_path: PathBuf,
+    info: ArchiveInfo,
+    /// In the header block before `----------`.
+    in_header: bool,
+    // 注释可能有多行.
+    in_comment: bool,
+    /// The entry being parsed, until the next one starts.
+    file: Option<OutputFile>,
+    entries: usize,
+    on_entries: &'a dyn Fn(usize),
+}
+
+impl<'a> ListParser<'a> {
+    pub fn new(archive_path: &Path, on_entries: &'a dyn Fn(usize)) -> Self {
+        Self {
+            archive: ArchiveContents::new(archive_path.to_path_buf()),
+            archive_path: archive_path.to_path_buf(),
+            info: ArchiveInfo::default(),
+            in_header: true,
+            in_comment: false,
+            file: None,
+            entries: 0,
+            on_entries,
+        }
+    }
+
+    /// Parses the next line, without the line ending.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::InvalidUtf8)` if a path needs another codepage.
+    pub fn push_line(&mut self, line: &str) -> Result<(), SevenzError> {
+        if self.in_header {
+            self.push_header_line(line);
+        } else if line.is_empty() {
+            self.flush_file();
+        } else {
+            self.push_entry_line(line)?;
+        }
+        Ok(())
     }
-    if let Some(mcp) = codepage {
-        println!("mcp: {}", mcp);
-        command.arg(mcp.to_string());
+
+    /// Returns the archive contents, once 7z has exited.
+    pub fn finish(mut self) -> ArchiveContents {
+        self.flush_file();
+        self.archive.set_info(self.info);
+        self.archive
     }
-    command.arg(archive_path.as_ref());
-    let output = command.output();
-    match output {
-        Ok(output) => {
-            if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
-            } else if need_password(&output) || wrong_password(&output) {
-                Err(SevenzError::NeedPassword(
-                    archive_path.as_ref().as_os_str().into(),
-                ))
-            } else {
-                Err(SevenzError::CommandError(
-                    String::from_utf8_lossy(&output.stderr).to_string(),
-                ))
+
+    fn push_header_line(&mut self, line: &str) {
+        if line == "----------" {
+            self.in_header = false;
+            return;
+        }
+        let info = &mut self.info;
+        let line_type = LineType::new(line);
+        match &line_type {
+            Some(LineType::Volumes(volumes @ 2..)) => {
+                let multi_volume = archive_multi_volume(&self.archive_path, *volumes);
+                self.archive.set_multi_volume(multi_volume);
             }
+            Some(LineType::Type(archive_type)) => info.archive_type = Some(archive_type.clone()),
+            Some(LineType::PhysicalSize(size)) => info.physical_size = Some(*size),
+            Some(LineType::HeadersSize(size)) => info.headers_size = Some(*size),
+            Some(LineType::Method(method)) => info.method = Some(method.clone()),
+            Some(LineType::Solid(solid)) => info.solid = *solid,
+            Some(LineType::Blocks(blocks)) => info.blocks = Some(*blocks),
+            Some(LineType::Encrypted(encrypted)) => info.encrypted_headers = *encrypted,
+            Some(LineType::Comment(comment)) => info.comment = Some(comment.clone()),
+            Some(LineType::Characteristics(characteristics)) => {
+                // RAR: "Volume Solid Recovery Lock ...".
+                let flags = characteristics.split_whitespace().collect::<Vec<_>>();
+                info.recovery_record |= flags.contains(&"Recovery");
+                info.locked |= flags.contains(&"Lock") || flags.contains(&"Locked");
+            }
+            None if self.in_comment && !line.is_empty() => {
+                if let Some(comment) = &mut info.comment {
+                    comment.push('\n');
+                    comment.push_str(line);
+                }
+                return;
+            }
+            _ => {}
         }
-        Err(err) => Err(SevenzError::CommandError(err.to_string())),
+        self.in_comment = matches!(line_type, Some(LineType::Comment(_)));
     }
-}
 
-trait PasswordCommand {
-    fn set_password(&mut self, password: &str);
-}
-
-impl PasswordCommand for Command {
-    fn set_password(&mut self, password: &str) {
-        let password = format!(r#"-p{password}"#);
-        self.arg(password);
+    fn push_entry_line(&mut self, line: &str) -> Result<(), SevenzError> {
+        let line_type = LineType::new(line);
+        // Path 总是条目的第一行.
+        if let Some(LineType::Path(path)) = line_type {
+            check_invalid_utf8(&path)?;
+            self.flush_file();
+            self.file = Some(OutputFile {
+                path,
+                raw_path: None,
+                is_dir: false,
+                modified: None,
+                entry: ArchiveEntry::default(),
+            });
+            return Ok(());
+        }
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        let entry = &mut file.entry;
+        match line_type {
+            Some(LineType::Folder(b)) => {
+                file.is_dir |= b;
+            }
+            Some(LineType::Attributes(attributes)) => {
+                file.is_dir |= attributes.starts_with("D");
+                entry.attributes = Some(attributes);
+            }
+            Some(LineType::Modified(datetime)) => {
+                file.modified = Some(datetime);
+            }
+            Some(LineType::Size(size)) => entry.size = Some(size),
+            Some(LineType::PackedSize(size)) => entry.packed_size = Some(size),
+            Some(LineType::Created(datetime)) => entry.created = Some(datetime),
+            Some(LineType::Accessed(datetime)) => entry.accessed = Some(datetime),
+            Some(LineType::Crc(crc)) => entry.crc = Some(crc),
+            Some(LineType::Method(method)) => entry.method = Some(method),
+            Some(LineType::Encrypted(encrypted)) => entry.encrypted = encrypted,
+            Some(LineType::Comment(comment)) => entry.comment = Some(comment),
+            Some(LineType::Block(block)) => entry.block = Some(block),
+            _ => {}
+        };
+        Ok(())
     }
-}
 
-// 判断是否需要密码输入.
-fn need_password(output: &Output) -> bool {
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    output.status.code() == Some(255) && output_str.contains("Enter password")
+    /// Appends the parsed entry to the tree.
+    fn flush_file(&mut self) {
+        let Some(file) = self.file.take() else {
+            return;
+        };
+        self.archive.append_file(file);
+        self.entries += 1;
+        if self.entries.is_multiple_of(LIST_PROGRESS_STEP) {
+            (self.on_entries)(self.entries);
+        }
+    }
 }
 
-fn wrong_password(output: &Output) -> bool {
-    let output_str = String::from_utf8_lossy(&output.stderr);
-    output.status.code() == Some(2) && output_str.contains("Wrong password?")
+/// Returns `Err(SevenzError::InvalidUtf8)` if the path looks like it was decoded with the wrong
+/// codepage.
+fn check_invalid_utf8(path: &str) -> Result<(), SevenzError> {
+    let check = path
+        .chars()
+        .filter(|c| {
+            is_replacement_character(*c) || is_halfwidth_katakana(*c) || is_latin_capital_letter(*c)
+        })
+        .count();
+    let chars_count = path.chars().count();
+    if check as f64 / chars_count as f64 > 0.5 {
+        return Err(SevenzError::InvalidUtf8(path.to_owned()));
+    };
+    Ok(())
 }
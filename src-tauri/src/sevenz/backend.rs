@@ -0,0 +1,498 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    process::Child,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use super::{codepage::OptionalCodepage, error::SevenzError, fs_tree::ArchiveContents, Archive};
+
+pub mod sevenz_cli;
+pub mod tar;
+pub mod zip;
+
+use sevenz_cli::SevenzCli;
+use tar::TarBackend;
+use zip::ZipBackend;
+
+/// Sent by a running extraction, see [`ArchiveBackend::extract`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractUpdate {
+    /// The entry being extracted, its full path in the archive.
+    Entry(String),
+    /// Percent of the whole extraction, reported by 7z.
+    Percent(f64),
+    /// Bytes extracted so far, reported by the built-in backends.
+    Bytes(u64),
+}
+
+/// An engine able to list, test and extract archives.
+///
+/// The 7-Zip command line is one implementation, see [`SevenzCli`]. The pipeline in
+/// [`super::show_archive_content`], [`super::test_archive`] and
+/// [`super::unzip::unzip_selected`] only talks to this trait, so other engines can be plugged
+/// in without touching it.
+pub trait ArchiveBackend: Send + Sync {
+    /// Lists the entries of the archive.
+    ///
+    /// `on_entries` is called with the number of entries listed so far, from time to time.
+    ///
+    /// Returns `Err(SevenzError::NeedPassword)` if the password is missing or wrong, and
+    /// `Err(SevenzError::InvalidUtf8)` if the file names need another codepage.
+    fn list(
+        &self,
+        archive_path: &Path,
+        password: &str,
+        codepage: OptionalCodepage,
+        on_entries: &dyn Fn(usize),
+    ) -> Result<ArchiveContents, SevenzError>;
+
+    /// Starts extracting the archive into `output_dir`.
+    ///
+    /// A trailing `*` of `output_dir` is replaced with the archive name, like the `-o` switch
+    /// of 7z, see [`resolve_output_dir`].
+    ///
+    /// Only the entries at `includes`, full paths in the archive, and their children are
+    /// extracted, with the folders containing them. All entries if `includes` is empty.
+    ///
+    /// The extraction runs in the background and sends its progress to `updates`, the sender
+    /// is dropped when it finishes.
+    fn extract(
+        &self,
+        archive: &Archive,
+        output_dir: &Path,
+        password: Option<&str>,
+        includes: &[String],
+        updates: mpsc::Sender<ExtractUpdate>,
+    ) -> Result<Box<dyn ExtractProcess>, SevenzError>;
+
+    /// Tests the archive like `7z t`, every entry is decompressed and checked without being
+    /// written.
+    ///
+    /// Returns `Err(SevenzError::NeedPassword)` if the password is missing or wrong.
+    fn test(&self, archive: &Archive, password: Option<&str>) -> Result<(), SevenzError>;
+
+    /// Returns the version of the engine, the one of e-zip for the built-in backends.
+    fn version(&self) -> Result<String, SevenzError>;
+}
+
+/// A running extraction started by [`ArchiveBackend::extract`].
+pub trait ExtractProcess: Send {
+    /// Returns whether the extraction succeeded, `None` while it is still running.
+    ///
+    /// The built-in backends return their [`SevenzError`] inside the `io::Error`, 7z only
+    /// reports its exit code. It doesn't block, see [`super::job::Job::wait`].
+    fn try_wait(&mut self) -> io::Result<Option<bool>>;
+
+    /// Stops the extraction, it finishes unsuccessfully.
+    fn kill(&mut self) -> io::Result<()>;
+
+    /// Suspends the extraction until [`ExtractProcess::resume`].
+    fn pause(&mut self) -> io::Result<()>;
+
+    fn resume(&mut self) -> io::Result<()>;
+}
+
+impl ExtractProcess for Child {
+    fn kill(&mut self) -> io::Result<()> {
+        Child::kill(self)
+    }
+
+    fn pause(&mut self) -> io::Result<()> {
+        suspend_child(self, true)
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        suspend_child(self, false)
+    }
+
+    fn try_wait(&mut self) -> io::Result<Option<bool>> {
+        Ok(Child::try_wait(self)?.map(|status| status.success()))
+    }
+}
+
+#[cfg(unix)]
+fn suspend_child(child: &Child, suspend: bool) -> io::Result<()> {
+    let signal = if suspend {
+        libc::SIGSTOP
+    } else {
+        libc::SIGCONT
+    };
+    // SAFETY: the child is not waited yet, so the pid still belongs to it.
+    match unsafe { libc::kill(child.id() as libc::pid_t, signal) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Windows has no signal to stop a process, its threads are suspended one by one.
+#[cfg(windows)]
+fn suspend_child(child: &Child, suspend: bool) -> io::Result<()> {
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, INVALID_HANDLE_VALUE},
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD,
+                THREADENTRY32,
+            },
+            Threading::{OpenThread, ResumeThread, SuspendThread, THREAD_SUSPEND_RESUME},
+        },
+    };
+
+    // SAFETY: the handles are checked and closed, `entry` is initialized with its size.
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        let mut entry: THREADENTRY32 = std::mem::zeroed();
+        entry.dwSize = size_of::<THREADENTRY32>() as u32;
+        let mut found = Thread32First(snapshot, &mut entry);
+        while found != 0 {
+            if entry.th32OwnerProcessID == child.id() {
+                let thread = OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID);
+                if !thread.is_null() {
+                    if suspend {
+                        SuspendThread(thread);
+                    } else {
+                        ResumeThread(thread);
+                    }
+                    CloseHandle(thread);
+                }
+            }
+            found = Thread32Next(snapshot, &mut entry);
+        }
+        CloseHandle(snapshot);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+impl dyn ExtractProcess {
+    /// Blocks until the extraction finishes.
+    pub fn wait(&mut self) -> io::Result<bool> {
+        loop {
+            if let Some(success) = self.try_wait()? {
+                return Ok(success);
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+}
+
+/// Pauses and cancels a [`ThreadProcess`], the extraction checks it between entries.
+#[derive(Default)]
+pub struct ThreadControl {
+    paused: Mutex<bool>,
+    resumed: Condvar,
+    cancelled: AtomicBool,
+}
+
+impl ThreadControl {
+    /// Blocks while paused.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::Cancelled)` if the extraction is cancelled.
+    pub fn checkpoint(&self) -> Result<(), SevenzError> {
+        let mut paused = self.paused.lock().unwrap();
+        while *paused && !self.cancelled.load(Ordering::SeqCst) {
+            paused = self.resumed.wait(paused).unwrap();
+        }
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(SevenzError::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn set_paused(&self, paused: bool) {
+        *self.paused.lock().unwrap() = paused;
+        self.resumed.notify_all();
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        // 唤醒暂停中的线程.
+        let _paused = self.paused.lock().unwrap();
+        self.resumed.notify_all();
+    }
+}
+
+/// An extraction running in a thread of the current process.
+pub struct ThreadProcess {
+    state: ThreadState,
+    control: Arc<ThreadControl>,
+}
+
+enum ThreadState {
+    Running(JoinHandle<Result<(), SevenzError>>),
+    Finished(bool),
+}
+
+impl ThreadProcess {
+    pub fn spawn<F>(f: F) -> Self
+    where
+        F: FnOnce(&ThreadControl) -> Result<(), SevenzError> + Send + 'static,
+    {
+        let control = Arc::new(ThreadControl::default());
+        let thread_control = control.clone();
+        ThreadProcess {
+            state: ThreadState::Running(thread::spawn(move || f(&thread_control))),
+            control,
+        }
+    }
+
+    /// Returns the error of the extraction the first time, like [`Child::wait`] only reports
+    /// the exit status once.
+    fn join(&mut self) -> io::Result<bool> {
+        let handle = match std::mem::replace(&mut self.state, ThreadState::Finished(false)) {
+            ThreadState::Running(handle) => handle,
+            ThreadState::Finished(success) => {
+                self.state = ThreadState::Finished(success);
+                return Ok(success);
+            }
+        };
+        let result = match handle.join() {
+            Ok(Ok(())) => Ok(true),
+            Ok(Err(SevenzError::Cancelled)) => Ok(false),
+            Ok(Err(err)) => Err(io::Error::other(err)),
+            Err(_) => Err(io::Error::other("解压线程异常退出")),
+        };
+        self.state = ThreadState::Finished(matches!(result, Ok(true)));
+        result
+    }
+}
+
+impl ExtractProcess for ThreadProcess {
+    fn try_wait(&mut self) -> io::Result<Option<bool>> {
+        match &self.state {
+            ThreadState::Running(handle) if !handle.is_finished() => Ok(None),
+            _ => self.join().map(Some),
+        }
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        self.control.cancel();
+        Ok(())
+    }
+
+    fn pause(&mut self) -> io::Result<()> {
+        self.control.set_paused(true);
+        Ok(())
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        self.control.set_paused(false);
+        Ok(())
+    }
+}
+
+/// Returns the backend used for the archive.
+///
+/// Tarballs always use the built-in [`TarBackend`], 7z would only unpack the outer compression.
+/// Otherwise the 7z command is preferred, plain ZIP files fall back to the built-in
+/// [`ZipBackend`] when 7-Zip is not installed.
+///
+/// # Errors
+///
+/// Returns `Err(SevenzError::NotFound7z)` if no backend is available.
+pub fn archive_backend(archive_path: &Path) -> Result<Box<dyn ArchiveBackend>, SevenzError> {
+    if let Some(backend) = TarBackend::for_path(archive_path) {
+        return Ok(Box::new(backend));
+    }
+    match SevenzCli::new() {
+        Ok(backend) => Ok(Box::new(backend)),
+        Err(_) if is_zip(archive_path) => Ok(Box::new(ZipBackend)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Whether the file starts with a ZIP signature.
+fn is_zip(path: &Path) -> bool {
+    let mut magic = [0; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| matches!(&magic, b"PK\x03\x04" | b"PK\x05\x06"))
+}
+
+/// Decodes the raw entry name.
+///
+/// Names that are valid UTF-8 are used as is, like 7z does for ZIP entries with the UTF-8 flag.
+/// Otherwise the name is decoded with `codepage`, and `Err(SevenzError::InvalidUtf8)` is
+/// returned if that is not possible, so the caller can retry with another codepage.
+fn entry_name(raw: &[u8], codepage: &OptionalCodepage) -> Result<String, SevenzError> {
+    if let Ok(name) = std::str::from_utf8(raw) {
+        return Ok(name.to_owned());
+    }
+    codepage
+        .as_ref()
+        .and_then(|codepage| codepage.decode(raw))
+        .ok_or_else(|| SevenzError::InvalidUtf8(String::from_utf8_lossy(raw).into_owned()))
+}
+
+/// Replaces the trailing `*` of `output_dir` with the archive name without its extension.
+fn resolve_output_dir(output_dir: &Path, archive: &Path) -> PathBuf {
+    match output_dir.file_name() {
+        Some(name) if name == "*" => {
+            output_dir.with_file_name(archive.file_stem().unwrap_or_default())
+        }
+        _ => output_dir.to_path_buf(),
+    }
+}
+
+/// Joins the entry name to `output_dir`.
+///
+/// Returns `None` if the name is absolute or leaves `output_dir`.
+fn enclosed_path(output_dir: &Path, name: &str) -> Option<PathBuf> {
+    let mut path = output_dir.to_path_buf();
+    for part in name.split(['/', '\\']) {
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (None | Some(Component::CurDir), None) => {}
+            (Some(Component::Normal(part)), None) => path.push(part),
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Whether the entry is one of `includes` or inside one of them, see [`ArchiveBackend::extract`].
+fn is_included(name: &str, includes: &[String]) -> bool {
+    if includes.is_empty() {
+        return true;
+    }
+    let name = name.replace('\\', "/");
+    let name = name.trim_start_matches("./").trim_end_matches('/');
+    includes.iter().any(|include| {
+        name.strip_prefix(include.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+#[cfg(test)]
+mod test_backend {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{config::AppConfig, sevenz::OutputFile};
+
+    /// Lists a fixed tree, and requires the password `secret`.
+    struct MockBackend;
+
+    impl ArchiveBackend for MockBackend {
+        fn list(
+            &self,
+            archive_path: &Path,
+            password: &str,
+            _codepage: OptionalCodepage,
+            _on_entries: &dyn Fn(usize),
+        ) -> Result<ArchiveContents, SevenzError> {
+            if password != "secret" {
+                return Err(SevenzError::NeedPassword(archive_path.as_os_str().into()));
+            }
+            let mut archive = ArchiveContents::new(archive_path.to_path_buf());
+            for (path, is_dir) in [("root", true), ("root/b.txt", false), ("root/a.txt", false)] {
+                archive.append_file(OutputFile {
+                    path: path.to_string(),
+                    raw_path: None,
+                    is_dir,
+                    modified: None,
+                    entry: Default::default(),
+                });
+            }
+            Ok(archive)
+        }
+
+        fn extract(
+            &self,
+            _archive: &Archive,
+            _output_dir: &Path,
+            _password: Option<&str>,
+            _includes: &[String],
+            _updates: mpsc::Sender<ExtractUpdate>,
+        ) -> Result<Box<dyn ExtractProcess>, SevenzError> {
+            Err(SevenzError::CommandError("mock".to_string()))
+        }
+
+        fn test(&self, archive: &Archive, password: Option<&str>) -> Result<(), SevenzError> {
+            match password {
+                Some("secret") => Ok(()),
+                _ => Err(SevenzError::NeedPassword(archive.path.as_os_str().into())),
+            }
+        }
+
+        fn version(&self) -> Result<String, SevenzError> {
+            Ok("mock".to_string())
+        }
+    }
+
+    #[test]
+    fn list_through_backend() {
+        let archive_path = PathBuf::from(file!());
+        let config = AppConfig::default();
+
+        let result = crate::sevenz::show_archive_content_with(
+            &MockBackend,
+            &archive_path,
+            "",
+            None,
+            &config,
+            &Default::default(),
+            &|_| {},
+        );
+        assert!(matches!(result, Err(SevenzError::NeedPassword(_))));
+
+        let archive = crate::sevenz::show_archive_content_with(
+            &MockBackend,
+            &archive_path,
+            "secret",
+            None,
+            &config,
+            &Default::default(),
+            &|_| {},
+        )
+        .unwrap();
+        assert_eq!(
+            archive.to_string(),
+            format!(
+                "{}\n📁 root\n├── 📄 a.txt\n└── 📄 b.txt\n\n",
+                archive_path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_through_backend() {
+        let mut archive = Archive {
+            path: PathBuf::from(file!()),
+            password: None,
+            codepage: None,
+        };
+        let config = AppConfig::default();
+        assert!(matches!(
+            crate::sevenz::test_archive_with(&MockBackend, &archive, &config),
+            Err(SevenzError::NeedPassword(_))
+        ));
+        archive.password = Some("secret".to_string());
+        crate::sevenz::test_archive_with(&MockBackend, &archive, &config).unwrap();
+        assert_eq!(MockBackend.version().unwrap(), "mock");
+    }
+
+    #[test]
+    fn output_dir() {
+        let archive = Path::new("/archives/a.tar.gz");
+        assert_eq!(
+            resolve_output_dir(Path::new("/out/*"), archive),
+            Path::new("/out/a.tar")
+        );
+        assert_eq!(
+            resolve_output_dir(Path::new("/out"), archive),
+            Path::new("/out")
+        );
+    }
+}
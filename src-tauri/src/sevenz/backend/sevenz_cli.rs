@@ -0,0 +1,419 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{ArchiveBackend, ExtractProcess, ExtractUpdate};
+use crate::sevenz::{
+    codepage::OptionalCodepage, error::SevenzError, fs_tree::ArchiveContents, info::SevenZipInfo,
+    Archive, ListParser, SEVENZ_COMMAND,
+};
+
+const LIST_COMMAND_ARGS: [&str; 3] = ["l", "-slt", "-sccUTF-8"];
+const TEST_COMMAND_ARGS: [&str; 2] = ["t", "-sccUTF-8"];
+/// `-bsp1 -bb1` prints the progress and the extracted files, see [`read_progress`].
+const EXTRACT_COMMAND_ARGS: [&str; 5] = ["x", "-aou", "-sccUTF-8", "-bsp1", "-bb1"];
+
+/// The 7-Zip command line backend.
+pub struct SevenzCli {
+    command: String,
+}
+
+impl SevenzCli {
+    /// Creates a backend with the 7z command stored in the `SEVENZ_COMMAND` global variable.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::NotFound7z)` if the 7z command was not found.
+    pub fn new() -> Result<Self, SevenzError> {
+        let Some(command) = SEVENZ_COMMAND.lock().unwrap().clone() else {
+            return Err(SevenzError::NotFound7z);
+        };
+        Ok(Self { command })
+    }
+
+    /// Creates a backend with the given 7z command.
+    pub fn with_command<S: Into<String>>(command: S) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    fn command(&self) -> Command {
+        Command::new(&self.command)
+    }
+
+    /// Returns the version, formats and codecs of the 7z command, parsed from `7z i`.
+    pub fn info(&self) -> Result<SevenZipInfo, SevenzError> {
+        SevenZipInfo::cached(&self.command, || {
+            let output = self.command().arg("i").output().ok()?;
+            SevenZipInfo::parse(&String::from_utf8_lossy(&output.stdout))
+        })
+        .ok_or_else(|| SevenzError::CommandError("7-Zip 已安装，但版本信息解析失败。".to_string()))
+    }
+}
+
+impl ArchiveBackend for SevenzCli {
+    fn list(
+        &self,
+        archive_path: &Path,
+        password: &str,
+        codepage: OptionalCodepage,
+        on_entries: &dyn Fn(usize),
+    ) -> Result<ArchiveContents, SevenzError> {
+        if let Ok(info) = self.info()
+            && let Some(format) = info.unsupported_format(archive_path)
+        {
+            return Err(SevenzError::UnsupportedFormat(
+                archive_path.to_path_buf(),
+                format.to_string(),
+            ));
+        }
+        let mut command = self.command();
+        command.args(LIST_COMMAND_ARGS);
+        if !password.is_empty() {
+            command.set_password(password);
+        }
+        if let Some(mcp) = codepage {
+            command.arg(mcp.to_string());
+        }
+        command.arg(archive_path);
+        let mut child = command
+            .stdin(Stdio::null()) // 阻止输入密码.
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| SevenzError::CommandError(err.to_string()))?;
+
+        // 同时读取 stderr, 避免 7z 因管道写满而阻塞.
+        let mut stderr = child.stderr.take().unwrap();
+        let stderr = thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf);
+            String::from_utf8_lossy(&buf).into_owned()
+        });
+
+        // 逐行解析, 不缓存整个输出.
+        let mut parser = ListParser::new(archive_path, on_entries);
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = Vec::new();
+        let mut password_prompt = false;
+        let parsed = loop {
+            line.clear();
+            match stdout.read_until(b'\n', &mut line) {
+                Ok(0) => break Ok(()),
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&line);
+                    let line = line.trim_end_matches(['\n', '\r']);
+                    password_prompt |= line.contains("Enter password");
+                    if let Err(err) = parser.push_line(line) {
+                        break Err(err);
+                    }
+                }
+                Err(err) => break Err(err.into()),
+            }
+        };
+        // 解析出错时 7z 可能还在输出, 结束它并返回解析的错误, 例如需要换代码页的 InvalidUtf8.
+        if let Err(err) = parsed {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err);
+        }
+        let status = child.wait()?;
+        let stderr = stderr.join().unwrap_or_default();
+
+        if status.success() {
+            Ok(parser.finish())
+        } else if need_password(status, password_prompt) || wrong_password(status, &stderr) {
+            Err(SevenzError::NeedPassword(archive_path.as_os_str().into()))
+        } else {
+            Err(SevenzError::CommandError(stderr))
+        }
+    }
+
+    fn extract(
+        &self,
+        archive: &Archive,
+        output_dir: &Path,
+        password: Option<&str>,
+        includes: &[String],
+        updates: mpsc::Sender<ExtractUpdate>,
+    ) -> Result<Box<dyn ExtractProcess>, SevenzError> {
+        let mut command = self.command();
+        command.args(EXTRACT_COMMAND_ARGS);
+        command.output_dir_arg(output_dir);
+
+        if let Some(password) = password.filter(|p| !p.is_empty()) {
+            command.set_password(password);
+        }
+
+        if let Some(mcp) = &archive.codepage {
+            command.arg(mcp.to_string());
+        }
+
+        command.arg(&archive.path);
+
+        // 路径可能很多, 用列表文件避免命令行过长.
+        // `-spd` 关闭通配符, 文件名里的 `*` 和 `?` 按原样匹配.
+        let list_file = match includes {
+            [] => None,
+            _ => {
+                let list_file = list_file_path();
+                fs::write(&list_file, includes.join("\n"))?;
+                command
+                    .arg("-spd")
+                    .arg("-scsUTF-8")
+                    .arg(format!("@{}", list_file.display()));
+                Some(list_file)
+            }
+        };
+
+        let child = command
+            .stdin(Stdio::null()) // 阻止输入密码.
+            .stdout(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) => {
+                if let Some(list_file) = list_file {
+                    let _ = fs::remove_file(list_file);
+                }
+                return Err(err.into());
+            }
+        };
+        if let Some(stdout) = child.stdout.take() {
+            thread::spawn(move || read_progress(stdout, &updates));
+        }
+        match list_file {
+            None => Ok(Box::new(child)),
+            Some(list_file) => Ok(Box::new(ListFileChild { child, list_file })),
+        }
+    }
+
+    fn test(&self, archive: &Archive, password: Option<&str>) -> Result<(), SevenzError> {
+        let mut command = self.command();
+        command.args(TEST_COMMAND_ARGS);
+        if let Some(password) = password.filter(|p| !p.is_empty()) {
+            command.set_password(password);
+        }
+        if let Some(mcp) = &archive.codepage {
+            command.arg(mcp.to_string());
+        }
+        let output = command
+            .arg(&archive.path)
+            .stdin(Stdio::null()) // 阻止输入密码.
+            .output()?;
+        let password_prompt = String::from_utf8_lossy(&output.stdout).contains("Enter password");
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+        if output.status.success() {
+            Ok(())
+        } else if need_password(output.status, password_prompt)
+            || wrong_password(output.status, &stderr)
+        {
+            Err(SevenzError::NeedPassword(archive.path.as_os_str().into()))
+        } else {
+            Err(SevenzError::CommandError(stderr))
+        }
+    }
+
+    fn version(&self) -> Result<String, SevenzError> {
+        self.info().map(|info| info.version)
+    }
+}
+
+/// Parses the output of `7z x -bsp1 -bb1` until the end.
+///
+/// The progress is rewritten in place with backspaces, like ` 42% 7 - docs/readme.md`, and
+/// each extracted file is a line like `- docs/readme.md`.
+fn read_progress<R: Read>(mut output: R, updates: &mpsc::Sender<ExtractUpdate>) {
+    let mut pending = Vec::new();
+    let mut buffer = [0; 4096];
+    while let Ok(n @ 1..) = output.read(&mut buffer) {
+        for &byte in &buffer[..n] {
+            if !matches!(byte, b'\n' | b'\r' | b'\x08') {
+                pending.push(byte);
+                continue;
+            }
+            if let Some(update) = parse_progress(&String::from_utf8_lossy(&pending))
+                && updates.send(update).is_err()
+            {
+                return;
+            }
+            pending.clear();
+        }
+    }
+    if let Some(update) = parse_progress(&String::from_utf8_lossy(&pending)) {
+        let _ = updates.send(update);
+    }
+}
+
+fn parse_progress(text: &str) -> Option<ExtractUpdate> {
+    let text = text.trim_start();
+    if let Some(name) = text.strip_prefix("- ") {
+        return Some(ExtractUpdate::Entry(name.to_string()));
+    }
+    let (percent, _) = text.split_once('%')?;
+    percent.parse().ok().map(ExtractUpdate::Percent)
+}
+
+fn list_file_path() -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!("e-zip-include-{}-{nanos}.txt", std::process::id()))
+}
+
+/// A 7z process reading a list file, the file is removed when the process is dropped.
+struct ListFileChild {
+    child: Child,
+    list_file: PathBuf,
+}
+
+impl ExtractProcess for ListFileChild {
+    fn try_wait(&mut self) -> io::Result<Option<bool>> {
+        ExtractProcess::try_wait(&mut self.child)
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        ExtractProcess::kill(&mut self.child)
+    }
+
+    fn pause(&mut self) -> io::Result<()> {
+        self.child.pause()
+    }
+
+    fn resume(&mut self) -> io::Result<()> {
+        self.child.resume()
+    }
+}
+
+impl Drop for ListFileChild {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.list_file);
+    }
+}
+
+trait PasswordCommand {
+    fn set_password(&mut self, password: &str);
+}
+
+impl PasswordCommand for Command {
+    fn set_password(&mut self, password: &str) {
+        let password = format!(r#"-p{password}"#);
+        self.arg(password);
+    }
+}
+
+trait SevenzOutput<T> {
+    fn output_dir_arg(&mut self, dir: T) -> &mut Self;
+}
+
+impl SevenzOutput<&Path> for Command {
+    fn output_dir_arg(&mut self, dir: &Path) -> &mut Self {
+        let output_dir = format!(r#"-o{}"#, dir.to_string_lossy());
+        self.arg(output_dir)
+    }
+}
+
+// 判断是否需要密码输入.
+fn need_password(status: ExitStatus, password_prompt: bool) -> bool {
+    status.code() == Some(255) && password_prompt
+}
+
+fn wrong_password(status: ExitStatus, stderr: &str) -> bool {
+    status.code() == Some(2) && stderr.contains("Wrong password?")
+}
+
+#[cfg(test)]
+mod test_sevenz_cli {
+    use super::*;
+
+    #[test]
+    fn progress() {
+        let output = "\n7-Zip 24.09 (x64)\n\nExtracting archive: a.7z\n--\nPath = a.7z\n\n  0%\x08\x08\x08\x08    \x08\x08\x08\x08 42% 1 - docs/read me.md\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08- docs/read me.md\r\n100%\nEverything is Ok\n";
+        let (tx, rx) = mpsc::channel();
+        read_progress(output.as_bytes(), &tx);
+        drop(tx);
+        assert_eq!(
+            rx.into_iter().collect::<Vec<_>>(),
+            vec![
+                ExtractUpdate::Percent(0.0),
+                ExtractUpdate::Percent(42.0),
+                ExtractUpdate::Entry("docs/read me.md".to_string()),
+                ExtractUpdate::Percent(100.0),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn list_invalid_utf8() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("e-zip-test-7z-list-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // 输出一个无效的路径后继续运行, 像还在输出的大压缩包一样.
+        let command = dir.join("7z");
+        fs::write(
+            &command,
+            "#!/bin/sh\n[ \"$1\" = i ] && exit 1\nprintf -- '--\\nPath = a.7z\\n\\n----------\\nPath = \\377\\376\\375\\374\\n\\n'\nexec sleep 10\n",
+        )
+        .unwrap();
+        fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let backend = SevenzCli {
+            command: command.to_string_lossy().into_owned(),
+        };
+        let result = backend.list(&dir.join("a.7z"), "", None, &|_| {});
+        assert!(matches!(result, Err(SevenzError::InvalidUtf8(_))));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extract_literal_names() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("e-zip-test-7z-spd-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // 记录参数和列表文件的内容.
+        let command = dir.join("7z");
+        fs::write(
+            &command,
+            "#!/bin/sh\nfor arg; do echo \"$arg\"; done > \"$0.args\"\nfor arg; do case \"$arg\" in @*) cat \"${arg#@}\";; esac; done > \"$0.list\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let backend = SevenzCli {
+            command: command.to_string_lossy().into_owned(),
+        };
+        let archive = Archive {
+            path: dir.join("a.7z"),
+            password: None,
+            codepage: None,
+        };
+        let includes = ["docs/*.md".to_string(), "a?.txt".to_string()];
+        let (tx, _rx) = mpsc::channel();
+        let mut process = backend
+            .extract(&archive, &dir.join("out"), None, &includes, tx)
+            .unwrap();
+        assert!(process.wait().unwrap());
+        drop(process);
+
+        let args = fs::read_to_string(dir.join("7z.args")).unwrap();
+        assert!(args.lines().any(|arg| arg == "-spd"));
+        let list = fs::read_to_string(dir.join("7z.list")).unwrap();
+        assert_eq!(list.lines().collect::<Vec<_>>(), ["docs/*.md", "a?.txt"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
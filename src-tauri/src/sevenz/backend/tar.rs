@@ -0,0 +1,425 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::Path,
+    sync::mpsc,
+};
+
+use filetime::FileTime;
+use time::OffsetDateTime;
+
+use super::{
+    enclosed_path, entry_name, is_included, resolve_output_dir, ArchiveBackend, ExtractProcess,
+    ExtractUpdate, ThreadProcess,
+};
+use crate::sevenz::{
+    codepage::OptionalCodepage,
+    entry::ArchiveEntry,
+    error::SevenzError,
+    fs_tree::{ArchiveContents, ArchiveInfo},
+    Archive, OutputFile, LIST_PROGRESS_STEP,
+};
+
+/// Compression of a tarball, guessed from the file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TarCompression {
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+}
+
+impl TarCompression {
+    const SUFFIXES: [(&'static str, TarCompression); 11] = [
+        (".tar", Self::None),
+        (".tar.gz", Self::Gzip),
+        (".tgz", Self::Gzip),
+        (".tar.xz", Self::Xz),
+        (".txz", Self::Xz),
+        (".tar.zst", Self::Zstd),
+        (".tzst", Self::Zstd),
+        (".tar.zstd", Self::Zstd),
+        (".tar.bz2", Self::Bzip2),
+        (".tbz2", Self::Bzip2),
+        (".tbz", Self::Bzip2),
+    ];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        Self::SUFFIXES
+            .iter()
+            .find(|(suffix, _)| file_name.ends_with(suffix))
+            .map(|(_, compression)| *compression)
+    }
+
+    /// The compression, named like the 7z formats.
+    fn method(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Xz => Some("xz"),
+            Self::Zstd => Some("zstd"),
+            Self::Bzip2 => Some("bzip2"),
+        }
+    }
+
+    fn decoder(self, reader: BufReader<File>) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Self::None => Box::new(reader),
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        })
+    }
+}
+
+/// The built-in tar backend.
+///
+/// Compressed tarballs are decompressed and unpacked in one streaming pass, keeping Unix
+/// permissions, symlinks and modification times.
+pub struct TarBackend {
+    compression: TarCompression,
+}
+
+impl TarBackend {
+    /// Returns the backend if the file name looks like a tarball.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        TarCompression::from_path(path).map(|compression| Self { compression })
+    }
+
+    fn open(&self, archive_path: &Path) -> Result<tar::Archive<Box<dyn Read + Send>>, SevenzError> {
+        let file = BufReader::new(File::open(archive_path)?);
+        let decoder = self.compression.decoder(file)?;
+        Ok(tar::Archive::new(decoder))
+    }
+}
+
+impl ArchiveBackend for TarBackend {
+    fn list(
+        &self,
+        archive_path: &Path,
+        _password: &str,
+        codepage: OptionalCodepage,
+        on_entries: &dyn Fn(usize),
+    ) -> Result<ArchiveContents, SevenzError> {
+        let mut tar = self.open(archive_path)?;
+        let mut archive = ArchiveContents::new(archive_path.to_path_buf());
+        archive.set_info(ArchiveInfo {
+            archive_type: Some("tar".to_string()),
+            physical_size: fs::metadata(archive_path).ok().map(|m| m.len()),
+            method: self.compression.method().map(str::to_owned),
+            ..Default::default()
+        });
+        let mut entries = 0_usize;
+        for entry in tar.entries()? {
+            let entry = entry?;
+            let Some(path) = tar_path(&entry, &codepage)? else {
+                continue;
+            };
+            let header = entry.header();
+            archive.append_file(OutputFile {
+                path,
+                raw_path: Some(entry.path_bytes().into_owned()),
+                is_dir: header.entry_type().is_dir(),
+                modified: header
+                    .mtime()
+                    .ok()
+                    .and_then(|mtime| OffsetDateTime::from_unix_timestamp(mtime as i64).ok()),
+                entry: ArchiveEntry {
+                    size: Some(entry.size()),
+                    attributes: header.mode().ok().map(|mode| format!("{:o}", mode)),
+                    ..Default::default()
+                },
+            });
+            entries += 1;
+            if entries.is_multiple_of(LIST_PROGRESS_STEP) {
+                on_entries(entries);
+            }
+        }
+        Ok(archive)
+    }
+
+    fn extract(
+        &self,
+        archive: &Archive,
+        output_dir: &Path,
+        _password: Option<&str>,
+        includes: &[String],
+        updates: mpsc::Sender<ExtractUpdate>,
+    ) -> Result<Box<dyn ExtractProcess>, SevenzError> {
+        let mut tar = self.open(&archive.path)?;
+        tar.set_preserve_permissions(true);
+        tar.set_preserve_mtime(true);
+        let codepage = archive.codepage.clone();
+        let output_dir = resolve_output_dir(output_dir, &archive.path);
+        let includes = includes.to_vec();
+        let process = ThreadProcess::spawn(move |control| {
+            fs::create_dir_all(&output_dir)?;
+            // Directory times are set last, extracting their children changes them.
+            let mut dir_times = Vec::new();
+            let mut bytes = 0;
+            for entry in tar.entries()? {
+                control.checkpoint()?;
+                let mut entry = entry?;
+                let Some(name) =
+                    tar_path(&entry, &codepage)?.filter(|name| is_included(name, &includes))
+                else {
+                    continue;
+                };
+                let Some(path) = enclosed_path(&output_dir, &name) else {
+                    log::warn!("skip unsafe path: {}", name);
+                    continue;
+                };
+                if entry.header().entry_type().is_dir()
+                    && let Ok(mtime) = entry.header().mtime()
+                {
+                    dir_times.push((path.to_owned(), mtime));
+                }
+                let _ = updates.send(ExtractUpdate::Entry(name));
+                unpack_entry(&mut entry, &output_dir, &path, &codepage)?;
+                bytes += entry.header().size().unwrap_or_default();
+                let _ = updates.send(ExtractUpdate::Bytes(bytes));
+            }
+            for (path, mtime) in dir_times.into_iter().rev() {
+                let mtime = FileTime::from_unix_time(mtime as i64, 0);
+                filetime::set_file_mtime(path, mtime)?;
+            }
+            Ok(())
+        });
+        Ok(Box::new(process))
+    }
+
+    fn test(&self, archive: &Archive, _password: Option<&str>) -> Result<(), SevenzError> {
+        let mut tar = self.open(&archive.path)?;
+        for entry in tar.entries()? {
+            io::copy(&mut entry?, &mut io::sink())?;
+        }
+        // 读到压缩流的结尾, gzip 和 xz 在那里校验 CRC.
+        io::copy(&mut tar.into_inner(), &mut io::sink())?;
+        Ok(())
+    }
+
+    fn version(&self) -> Result<String, SevenzError> {
+        Ok(env!("CARGO_PKG_VERSION").to_string())
+    }
+}
+
+/// Returns the decoded path of the entry, or `None` for the `./` entry and PAX global headers.
+fn tar_path<R: Read>(
+    entry: &tar::Entry<'_, R>,
+    codepage: &OptionalCodepage,
+) -> Result<Option<String>, SevenzError> {
+    if entry.header().entry_type().is_pax_global_extensions() {
+        return Ok(None);
+    }
+    let name = entry_name(&entry.path_bytes(), codepage)?;
+    let name = name.trim_start_matches("./").trim_end_matches('/');
+    Ok((!name.is_empty() && name != ".").then(|| name.to_owned()))
+}
+
+/// Unpacks the entry to `path`.
+///
+/// Entries with UTF-8 names and link names go through [`tar::Entry::unpack_in`], which also
+/// resolves hard links inside `output_dir`. Names decoded with a codepage are written to `path`
+/// with the same checks: the parent folder must not leave `output_dir` through an extracted link,
+/// and link names are decoded and resolved inside `output_dir` too.
+fn unpack_entry<R: Read>(
+    entry: &mut tar::Entry<'_, R>,
+    output_dir: &Path,
+    path: &Path,
+    codepage: &OptionalCodepage,
+) -> Result<(), SevenzError> {
+    let is_utf8 = |bytes: &[u8]| std::str::from_utf8(bytes).is_ok();
+    if is_utf8(&entry.path_bytes()) && entry.link_name_bytes().is_none_or(|name| is_utf8(&name)) {
+        entry.unpack_in(output_dir)?;
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+        if !parent
+            .canonicalize()?
+            .starts_with(output_dir.canonicalize()?)
+        {
+            log::warn!("skip path outside output dir: {}", path.display());
+            return Ok(());
+        }
+    }
+    let entry_type = entry.header().entry_type();
+    if !entry_type.is_hard_link() && !entry_type.is_symlink() {
+        entry.unpack(path)?;
+        return Ok(());
+    }
+    let Some(link_name) = entry.link_name_bytes() else {
+        return Ok(());
+    };
+    let link_name = entry_name(&link_name, codepage)?;
+    if entry_type.is_hard_link() {
+        let Some(target) = enclosed_path(output_dir, &link_name) else {
+            log::warn!("skip unsafe hard link: {}", link_name);
+            return Ok(());
+        };
+        fs::hard_link(target, path)?;
+    } else {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(link_name, path)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(link_name, path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_tar {
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    const MTIME: u64 = 1_700_000_000;
+
+    fn write_tar_gz(path: &Path) {
+        let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_mtime(MTIME);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "./root/", io::empty())
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o750);
+        header.set_mtime(MTIME);
+        header.set_size(6);
+        builder
+            .append_data(&mut header, "./root/run.sh", &b"exit 0"[..])
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_mode(0o777);
+        header.set_mtime(MTIME);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "./root/link.sh", "run.sh")
+            .unwrap();
+
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn list_extract() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-tar-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("archive.tar.gz");
+        write_tar_gz(&archive_path);
+
+        let backend = TarBackend::for_path(&archive_path).unwrap();
+        assert_eq!(backend.compression, TarCompression::Gzip);
+        let contents = backend.list(&archive_path, "", None, &|_| {}).unwrap();
+        assert_eq!(
+            contents.to_string(),
+            format!(
+                "{}\n📁 root\n├── 📄 run.sh\n└── 📄 link.sh\n\n",
+                archive_path.display()
+            )
+        );
+
+        let mut archive = Archive {
+            path: archive_path,
+            password: None,
+            codepage: None,
+        };
+        backend.test(&archive, None).unwrap();
+        // 截断的 gzip 流在结尾校验时出错.
+        let bytes = fs::read(&archive.path).unwrap();
+        let truncated = dir.join("truncated.tar.gz");
+        fs::write(&truncated, &bytes[..bytes.len() - 4]).unwrap();
+        archive.path = truncated;
+        assert!(backend.test(&archive, None).is_err());
+        archive.path = dir.join("archive.tar.gz");
+
+        let output_dir = dir.join("output");
+        let mut process = backend
+            .extract(&archive, &output_dir, None, &[], mpsc::channel().0)
+            .unwrap();
+        assert!(process.wait().unwrap());
+        let run = output_dir.join("root/run.sh");
+        assert_eq!(fs::read_to_string(&run).unwrap(), "exit 0");
+        let metadata = fs::metadata(&run).unwrap();
+        assert_eq!(
+            FileTime::from_last_modification_time(&metadata).unix_seconds(),
+            MTIME as i64
+        );
+        let root = fs::metadata(output_dir.join("root")).unwrap();
+        assert_eq!(
+            FileTime::from_last_modification_time(&root).unix_seconds(),
+            MTIME as i64
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o750);
+            let link = output_dir.join("root/link.sh");
+            assert_eq!(fs::read_link(link).unwrap(), Path::new("run.sh"));
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn codepage_links() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        use crate::sevenz::codepage::Codepage;
+
+        const SHIFT_JIS_NAME: &[u8] = b"\x83\x65\x83\x58\x83\x67";
+
+        let dir = std::env::temp_dir().join(format!("e-zip-test-tar-link-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("archive.tar");
+        let mut builder = tar::Builder::new(File::create(&archive_path).unwrap());
+        let root = Path::new("root");
+        let name = root.join(OsStr::from_bytes(SHIFT_JIS_NAME));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, &name, "../..").unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(7);
+        builder
+            .append_data(&mut header, name.join("escaped.txt"), &b"escaped"[..])
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Link);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, root.join("hard"), &name)
+            .unwrap();
+        builder.into_inner().unwrap();
+
+        let archive = Archive {
+            path: archive_path.clone(),
+            password: None,
+            codepage: Some(Codepage::SHIFT_JIS),
+        };
+        let output_dir = dir.join("output");
+        let mut process = TarBackend::for_path(&archive_path)
+            .unwrap()
+            .extract(&archive, &output_dir, None, &[], mpsc::channel().0)
+            .unwrap();
+        assert!(process.wait().unwrap());
+        let link = output_dir.join("root/テスト");
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("../.."));
+        assert!(!dir.join("escaped.txt").exists());
+        assert!(output_dir.join("root/hard").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    fs::{self, File, FileTimes},
+    io::{self, BufReader, Read, Seek},
+    path::Path,
+    sync::mpsc,
+    time::SystemTime,
+};
+
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+use time_tz::{system::get_timezone, PrimitiveDateTimeExt};
+use zip::{read::ZipFile, result::ZipError, DateTime, ZipArchive};
+
+use super::{
+    enclosed_path, entry_name, is_included, resolve_output_dir, ArchiveBackend, ExtractProcess,
+    ExtractUpdate, ThreadProcess,
+};
+use crate::sevenz::{
+    codepage::OptionalCodepage,
+    entry::ArchiveEntry,
+    error::SevenzError,
+    fs_tree::{ArchiveContents, ArchiveInfo},
+    Archive, OutputFile, LIST_PROGRESS_STEP,
+};
+
+/// The built-in ZIP backend, used when 7-Zip is not installed.
+pub struct ZipBackend;
+
+impl ZipBackend {
+    fn open(archive_path: &Path) -> Result<ZipArchive<BufReader<File>>, SevenzError> {
+        let file = File::open(archive_path)?;
+        ZipArchive::new(BufReader::new(file)).map_err(zip_error)
+    }
+}
+
+impl ArchiveBackend for ZipBackend {
+    fn list(
+        &self,
+        archive_path: &Path,
+        password: &str,
+        codepage: OptionalCodepage,
+        on_entries: &dyn Fn(usize),
+    ) -> Result<ArchiveContents, SevenzError> {
+        let mut zip = Self::open(archive_path)?;
+        // 和 7z 一样, 列出时不需要密码, 但给出的密码要能解密.
+        if !password.is_empty()
+            && let Some(index) =
+                (0..zip.len()).find(|&i| zip.by_index_raw(i).is_ok_and(|file| file.encrypted()))
+        {
+            zip.by_index_decrypt(index, password.as_bytes())
+                .map_err(|err| entry_error(archive_path, err))?;
+        }
+        let mut archive = ArchiveContents::new(archive_path.to_path_buf());
+        archive.set_info(ArchiveInfo {
+            archive_type: Some("zip".to_string()),
+            physical_size: fs::metadata(archive_path).ok().map(|m| m.len()),
+            comment: Some(String::from_utf8_lossy(zip.comment()).into_owned())
+                .filter(|c| !c.is_empty()),
+            ..Default::default()
+        });
+        for index in 0..zip.len() {
+            let file = zip.by_index_raw(index).map_err(zip_error)?;
+            archive.append_file(OutputFile {
+                path: entry_name(file.name_raw(), &codepage)?,
+                raw_path: Some(file.name_raw().to_vec()),
+                is_dir: file.is_dir(),
+                modified: entry_modified(file.last_modified()),
+                entry: ArchiveEntry {
+                    size: Some(file.size()),
+                    packed_size: Some(file.compressed_size()),
+                    crc: Some(format!("{:08X}", file.crc32())),
+                    method: Some(file.compression().to_string()),
+                    encrypted: file.encrypted(),
+                    attributes: file.unix_mode().map(|mode| format!("{:o}", mode)),
+                    comment: Some(file.comment().to_string()).filter(|c| !c.is_empty()),
+                    ..Default::default()
+                },
+            });
+            if (index + 1).is_multiple_of(LIST_PROGRESS_STEP) {
+                on_entries(index + 1);
+            }
+        }
+        Ok(archive)
+    }
+
+    fn extract(
+        &self,
+        archive: &Archive,
+        output_dir: &Path,
+        password: Option<&str>,
+        includes: &[String],
+        updates: mpsc::Sender<ExtractUpdate>,
+    ) -> Result<Box<dyn ExtractProcess>, SevenzError> {
+        let mut zip = Self::open(&archive.path)?;
+        let archive_path = archive.path.clone();
+        let codepage = archive.codepage.clone();
+        let output_dir = resolve_output_dir(output_dir, &archive.path);
+        let password = password.map(str::to_owned);
+        let includes = includes.to_vec();
+        let process = ThreadProcess::spawn(move |control| {
+            let mut bytes = 0;
+            for index in 0..zip.len() {
+                control.checkpoint()?;
+                // 先检查名称, 未选中的加密文件不需要密码.
+                let raw = zip.by_index_raw(index).map_err(zip_error)?;
+                let name = entry_name(raw.name_raw(), &codepage)?;
+                drop(raw);
+                if !is_included(&name, &includes) {
+                    continue;
+                }
+                let mut file = entry(&mut zip, index, password.as_deref())
+                    .map_err(|err| entry_error(&archive_path, err))?;
+                let Some(path) = enclosed_path(&output_dir, &name) else {
+                    log::warn!("skip unsafe path: {}", name);
+                    continue;
+                };
+                let _ = updates.send(ExtractUpdate::Entry(name));
+                extract_entry(&mut file, &path)?;
+                bytes += file.size();
+                let _ = updates.send(ExtractUpdate::Bytes(bytes));
+            }
+            Ok(())
+        });
+        Ok(Box::new(process))
+    }
+
+    fn test(&self, archive: &Archive, password: Option<&str>) -> Result<(), SevenzError> {
+        let mut zip = Self::open(&archive.path)?;
+        for index in 0..zip.len() {
+            let mut file =
+                entry(&mut zip, index, password).map_err(|err| entry_error(&archive.path, err))?;
+            // 读到结尾时才校验 CRC.
+            io::copy(&mut file, &mut io::sink())?;
+        }
+        Ok(())
+    }
+
+    fn version(&self) -> Result<String, SevenzError> {
+        Ok(env!("CARGO_PKG_VERSION").to_string())
+    }
+}
+
+fn zip_error(err: ZipError) -> SevenzError {
+    SevenzError::ArchiveError(err.to_string())
+}
+
+/// Like [`zip_error`], but a missing or wrong password is `SevenzError::NeedPassword`, like
+/// the 7z backend returns, so the other passwords are tried.
+fn entry_error(archive_path: &Path, err: ZipError) -> SevenzError {
+    match err {
+        ZipError::InvalidPassword => SevenzError::NeedPassword(archive_path.as_os_str().into()),
+        ZipError::UnsupportedArchive(message) if message == ZipError::PASSWORD_REQUIRED => {
+            SevenzError::NeedPassword(archive_path.as_os_str().into())
+        }
+        err => zip_error(err),
+    }
+}
+
+fn entry<'a, R: Read + Seek>(
+    zip: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> Result<ZipFile<'a>, ZipError> {
+    match password.filter(|p| !p.is_empty()) {
+        Some(password) => zip.by_index_decrypt(index, password.as_bytes()),
+        None => zip.by_index(index),
+    }
+}
+
+/// ZIP stores the local time, like the `Modified` line of 7z.
+fn entry_modified(datetime: Option<DateTime>) -> Option<OffsetDateTime> {
+    let datetime = datetime?;
+    let date = Date::from_calendar_date(
+        datetime.year().into(),
+        Month::try_from(datetime.month()).ok()?,
+        datetime.day(),
+    )
+    .ok()?;
+    let time = Time::from_hms(datetime.hour(), datetime.minute(), datetime.second()).ok()?;
+    Some(PrimitiveDateTime::new(date, time).assume_timezone_utc(get_timezone().ok()?))
+}
+
+fn extract_entry(file: &mut ZipFile<'_>, path: &Path) -> Result<(), SevenzError> {
+    if file.is_dir() {
+        fs::create_dir_all(path)?;
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // 和 7z 不加 -snl 时一样不创建链接, 否则后面的条目可以通过链接写到 output_dir 之外.
+    if file.is_symlink() {
+        log::warn!("skip symlink: {}", path.display());
+        return Ok(());
+    }
+
+    let mut dest = File::create(path)?;
+    io::copy(file, &mut dest)?;
+    if let Some(modified) = entry_modified(file.last_modified()) {
+        dest.set_times(FileTimes::new().set_modified(SystemTime::from(modified)))?;
+    }
+
+    #[cfg(unix)]
+    if let Some(mode) = file.unix_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_zip {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, AesMode, ZipWriter};
+
+    use super::*;
+    use crate::sevenz::codepage::Codepage;
+
+    const SHIFT_JIS_NAME: &[u8] = b"\x83\x65\x83\x58\x83\x67";
+
+    fn write_zip(path: &Path) {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("root/", options).unwrap();
+        writer.start_file("root/plain.txt", options).unwrap();
+        writer.write_all(b"plain").unwrap();
+        writer
+            .start_file(
+                "root/secret.txt",
+                options.with_aes_encryption(AesMode::Aes256, "secret"),
+            )
+            .unwrap();
+        writer.write_all(b"secret").unwrap();
+        writer.start_file("root/XXXXXX.txt", options).unwrap();
+        writer.write_all(b"shift_jis").unwrap();
+        let mut bytes = writer.finish().unwrap().into_inner();
+
+        // Replace the name in the local and central headers with Shift_JIS bytes.
+        while let Some(index) = bytes.windows(6).position(|w| w == b"XXXXXX") {
+            bytes[index..index + 6].copy_from_slice(SHIFT_JIS_NAME);
+        }
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn list_test_extract() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-zip-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("archive.zip");
+        write_zip(&archive_path);
+
+        let backend = ZipBackend;
+        assert!(matches!(
+            backend.list(&archive_path, "", None, &|_| {}),
+            Err(SevenzError::InvalidUtf8(_))
+        ));
+        let contents = backend
+            .list(&archive_path, "", Some(Codepage::SHIFT_JIS), &|_| {})
+            .unwrap();
+        assert!(contents.to_string().contains("📄 テスト.txt"));
+        assert!(matches!(
+            backend.list(&archive_path, "wrong", Some(Codepage::SHIFT_JIS), &|_| {}),
+            Err(SevenzError::NeedPassword(_))
+        ));
+        backend
+            .list(&archive_path, "secret", Some(Codepage::SHIFT_JIS), &|_| {})
+            .unwrap();
+
+        let mut archive = Archive {
+            path: archive_path,
+            password: None,
+            codepage: Some(Codepage::SHIFT_JIS),
+        };
+        for password in [None, Some("wrong")] {
+            assert!(matches!(
+                backend.test(&archive, password),
+                Err(SevenzError::NeedPassword(_))
+            ));
+            let mut process = backend
+                .extract(
+                    &archive,
+                    &dir.join("wrong"),
+                    password,
+                    &[],
+                    mpsc::channel().0,
+                )
+                .unwrap();
+            let err = process.wait().unwrap_err();
+            assert!(matches!(
+                err.downcast::<SevenzError>(),
+                Ok(SevenzError::NeedPassword(_))
+            ));
+        }
+        backend.test(&archive, Some("secret")).unwrap();
+
+        archive.password = Some("secret".to_string());
+        let output_dir = dir.join("output");
+        let (sender, updates) = mpsc::channel();
+        let mut process = backend
+            .extract(
+                &archive,
+                &output_dir,
+                archive.password.as_deref(),
+                &[],
+                sender,
+            )
+            .unwrap();
+        assert!(process.wait().unwrap());
+        let updates: Vec<_> = updates.iter().collect();
+        assert!(updates.contains(&ExtractUpdate::Entry("root/secret.txt".to_string())));
+        assert!(updates.contains(&ExtractUpdate::Bytes(6 + 9 + 5)));
+        assert_eq!(
+            fs::read_to_string(output_dir.join("root/secret.txt")).unwrap(),
+            "secret"
+        );
+        assert_eq!(
+            fs::read_to_string(output_dir.join("root/テスト.txt")).unwrap(),
+            "shift_jis"
+        );
+
+        let selected_dir = dir.join("selected");
+        let includes = ["root/plain.txt".to_string()];
+        let mut process = backend
+            .extract(&archive, &selected_dir, None, &includes, mpsc::channel().0)
+            .unwrap();
+        assert!(process.wait().unwrap());
+        assert!(selected_dir.join("root/plain.txt").exists());
+        assert!(!selected_dir.join("root/secret.txt").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn skip_symlink() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-zip-link-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("archive.zip");
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_symlink("root/link", "../..", options).unwrap();
+        writer.start_file("root/link/escaped.txt", options).unwrap();
+        writer.write_all(b"escaped").unwrap();
+        fs::write(&archive_path, writer.finish().unwrap().into_inner()).unwrap();
+
+        let archive = Archive {
+            path: archive_path,
+            password: None,
+            codepage: None,
+        };
+        let output_dir = dir.join("output");
+        let mut process = ZipBackend
+            .extract(&archive, &output_dir, None, &[], mpsc::channel().0)
+            .unwrap();
+        assert!(process.wait().unwrap());
+        let link = output_dir.join("root/link");
+        assert!(!link.is_symlink());
+        assert!(link.join("escaped.txt").is_file());
+        assert!(!dir.join("escaped.txt").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -44,6 +44,15 @@ impl Codepage {
             _ => Into::<CodepageDiscriminants>::into(self) as u16,
         }
     }
+
+    /// Decodes a file name with the codepage.
+    ///
+    /// Returns `None` if the codepage is unknown or the bytes are not valid in it.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        let encoding = ::codepage::to_encoding(self.as_u16())?;
+        let (name, had_errors) = encoding.decode_without_bom_handling(bytes);
+        (!had_errors).then(|| name.into_owned())
+    }
 }
 
 impl Display for Codepage {
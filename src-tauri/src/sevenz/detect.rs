@@ -0,0 +1,175 @@
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use super::installer;
+
+/// Which 7-Zip distribution an executable belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub enum SevenzFlavor {
+    /// The official 7-Zip (`7z`, `7zz`, `7za`, `7zr`) from 7-zip.org.
+    SevenZip,
+    /// The p7zip port found on older Linux distributions.
+    P7zip,
+}
+
+/// A 7z executable found on this machine.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SevenzCandidate {
+    pub path: PathBuf,
+    pub version: String,
+    pub flavor: SevenzFlavor,
+}
+
+impl SevenzCandidate {
+    /// Runs the executable and reads its version banner.
+    ///
+    /// Returns `None` if the file can't be run or is not 7-Zip.
+    pub fn probe<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        let output = Command::new(path).arg("-h").output().ok()?;
+        let (version, flavor) = parse_banner(&String::from_utf8_lossy(&output.stdout))?;
+        Some(Self {
+            path: path.to_path_buf(),
+            version,
+            flavor,
+        })
+    }
+
+    /// The version as numbers, used to rank the candidates.
+    fn version_key(&self) -> Vec<u32> {
+        version_key(&self.version)
+    }
+}
+
+/// A version like `24.09` as numbers, for comparing versions.
+pub fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|n| n.parse().unwrap_or_default())
+        .collect()
+}
+
+const COMMAND_NAMES: &[&str] = if cfg!(windows) {
+    &["7z.exe", "7za.exe", "7zr.exe"]
+} else {
+    &["7zz", "7zzs", "7z", "7za", "7zr"]
+};
+
+/// Returns the path of the private 7z command downloaded into `config_dir`.
+pub fn private_command_path(config_dir: &Path) -> PathBuf {
+    config_dir
+        .join(installer::install_dir_name())
+        .join(installer::command_file_name())
+}
+
+/// Returns the path of the private 7z command replaced by the last upgrade, kept as a fallback.
+pub fn previous_command_path(config_dir: &Path) -> PathBuf {
+    config_dir
+        .join(installer::previous_dir_name())
+        .join(installer::command_file_name())
+}
+
+/// Directories searched besides `PATH`.
+///
+/// GUI apps on macOS don't inherit the shell `PATH`, and p7zip installs the real binaries
+/// outside of it.
+fn extra_dirs() -> Vec<PathBuf> {
+    if cfg!(windows) {
+        ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"]
+            .iter()
+            .filter_map(env::var_os)
+            .map(|dir| PathBuf::from(dir).join("7-Zip"))
+            .collect()
+    } else {
+        [
+            "/opt/homebrew/bin",
+            "/usr/local/bin",
+            "/usr/bin",
+            "/usr/lib/p7zip",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+}
+
+/// Finds every 7z executable, the newest version first.
+///
+/// Looks for all known binary names in `PATH`, in the usual install directories and for the
+/// private copy and its fallback in `config_dir`. At the same version the official 7-Zip ranks
+/// before p7zip.
+pub fn candidates(config_dir: &Path) -> Vec<SevenzCandidate> {
+    let mut dirs = env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+    dirs.extend(extra_dirs());
+    let paths = dirs
+        .iter()
+        .flat_map(|dir| COMMAND_NAMES.iter().map(move |name| dir.join(name)))
+        .chain([
+            private_command_path(config_dir),
+            previous_command_path(config_dir),
+        ])
+        .filter(|path| path.is_file());
+
+    let mut seen = HashSet::new();
+    let mut candidates = paths
+        .filter(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())))
+        .filter_map(SevenzCandidate::probe)
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| {
+        b.version_key()
+            .cmp(&a.version_key())
+            .then((a.flavor == SevenzFlavor::P7zip).cmp(&(b.flavor == SevenzFlavor::P7zip)))
+    });
+    candidates
+}
+
+/// Parses the version and flavor from the banner printed by `7z -h`.
+///
+/// - 7-Zip: `7-Zip (z) 24.08 (x64) : Copyright (c) 1999-2024 Igor Pavlov : 2024-08-11`
+/// - p7zip: `7-Zip [64] 16.02 : Copyright ...` followed by `p7zip Version 16.02 (...)`
+pub fn parse_banner(output: &str) -> Option<(String, SevenzFlavor)> {
+    let line = output.lines().find(|line| line.starts_with("7-Zip"))?;
+    let re = Regex::new(r"\d+\.\d+").unwrap();
+    let version = re.find(line)?.as_str().to_string();
+    let flavor = if output.contains("p7zip Version") {
+        SevenzFlavor::P7zip
+    } else {
+        SevenzFlavor::SevenZip
+    };
+    Some((version, flavor))
+}
+
+#[cfg(test)]
+mod test_detect {
+    use super::*;
+
+    #[test]
+    fn banner() {
+        let sevenzz =
+            "\n7-Zip (z) 24.08 (x64) : Copyright (c) 1999-2024 Igor Pavlov : 2024-08-11\n";
+        assert_eq!(
+            parse_banner(sevenzz),
+            Some(("24.08".to_string(), SevenzFlavor::SevenZip))
+        );
+
+        let p7zip = "\n7-Zip [64] 16.02 : Copyright (c) 1999-2016 Igor Pavlov : 2016-05-21\n\
+                     p7zip Version 16.02 (locale=en_US.UTF-8,Utf16=on,HugeFiles=on,64 bits)\n";
+        assert_eq!(
+            parse_banner(p7zip),
+            Some(("16.02".to_string(), SevenzFlavor::P7zip))
+        );
+
+        assert_eq!(parse_banner("Usage: tar [OPTION...]"), None);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+use specta::Type;
+use time::OffsetDateTime;
+
+/// Metadata of an entry in the archive, the fields of `7z l -slt`.
+///
+/// Fields the archive format doesn't store are `None`.
+#[derive(Debug, Serialize, Clone, Type, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntry {
+    #[specta(type = Option<f64>)]
+    pub size: Option<u64>,
+    #[specta(type = Option<f64>)]
+    pub packed_size: Option<u64>,
+    /// CRC32 in upper case hex, like `3610A686`.
+    pub crc: Option<String>,
+    /// Compression method, like `LZMA2:24` or `Deflate`.
+    pub method: Option<String>,
+    pub encrypted: bool,
+    pub created: Option<OffsetDateTime>,
+    pub accessed: Option<OffsetDateTime>,
+    /// Attributes as printed by 7z, like `D drwxr-xr-x` or `A -rw-r--r--`.
+    pub attributes: Option<String>,
+    pub comment: Option<String>,
+    /// The solid block the entry belongs to.
+    #[specta(type = Option<f64>)]
+    pub block: Option<u64>,
+}
@@ -9,6 +9,8 @@ use thiserror::Error;
 pub enum SevenzError {
     #[error("7-Zip 未安装")]
     NotFound7z,
+    #[error("无效的 7-Zip 可执行文件: {0:?}")]
+    Invalid7z(PathBuf),
     #[error("需要密码输入: {0:?}")]
     NeedPassword(OsString),
     #[error("执行 7z 命令时出错: {0}")]
@@ -22,6 +24,28 @@ pub enum SevenzError {
     InvalidUtf8(String),
     #[error("不支持的文件: {0:?}")]
     UnsupportedFile(PathBuf),
+    #[error("当前的 7-Zip 不支持此格式: {1}")]
+    UnsupportedFormat(PathBuf, String),
+    #[error("读取压缩文件时出错: {0}")]
+    ArchiveError(String),
+    #[error("7-Zip 安装包校验失败: {0:?}")]
+    ChecksumMismatch(PathBuf),
+    #[error("安装 7-Zip 失败: {0}")]
+    InstallError(String),
+    #[error("已取消")]
+    Cancelled,
+    #[error("解压失败, 密码错误或压缩文件已损坏")]
+    ExtractFailed,
+    #[error("无效的搜索条件: {0}")]
+    InvalidQuery(String),
+    #[error("压缩文件中没有此路径: {0}")]
+    EntryNotFound(String),
+    #[error("没有此任务: {0}")]
+    JobNotFound(u32),
+    #[error("压缩文件已在解压队列中: {0:?}")]
+    AlreadyQueued(PathBuf),
+    #[error("压缩文件不在解压队列中: {0:?}")]
+    NotQueued(PathBuf),
 }
 
 #[derive(Debug)]
@@ -1,25 +1,110 @@
 use std::{
+    collections::HashMap,
     fmt,
     ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use ego_tree::{NodeId, Tree};
-use regex::Regex;
-use serde::Serialize;
+use ego_tree::{NodeId, NodeRef, Tree};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use specta::{datatype::DataType, NamedType, Type};
 use specta_util::Unknown;
 use time::OffsetDateTime;
 
-use crate::sevenz::archives_have_root_dir;
+use crate::sevenz::{archives_have_root_dir, FilesModified};
 
-use super::{multi_volume::ArchiveMultiVolume, OptionalCodepage, OutputFile};
+use sort::SortOptions;
+
+use super::{entry::ArchiveEntry, multi_volume::ArchiveMultiVolume, OptionalCodepage, OutputFile};
 
 #[derive(Debug, Serialize, Clone, Type, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct Fs {
     name: String,
     modified: Option<OffsetDateTime>,
+    /// The full path of the parent folder, empty at the top level.
     parent: Option<String>,
+    /// The full path in the archive, separated by `/`.
+    path: String,
+    /// The path as stored in the archive, before decoding it with the codepage.
+    ///
+    /// Only sent to the frontend when it differs from the UTF-8 of `path`, large archives
+    /// have many nodes.
+    // default 让 specta 把它导出为可选字段.
+    #[serde(skip_serializing_if = "RawPath::is_utf8_path", default)]
+    #[specta(type = Vec<u8>, optional)]
+    raw_path: RawPath,
+    /// Derived from `path`, so it stays the same when the archive is listed again.
+    id: String,
+    entry: ArchiveEntry,
+    stats: FsStats,
+}
+
+/// The bytes of a path as stored in the archive.
+///
+/// The built-in zip and tar backends read the stored bytes. 7z decodes the names itself, so
+/// its entries have the UTF-8 it outputs with `-sccUTF-8`. Folders only implied by the paths
+/// of their children get the leading part of the child's bytes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawPath {
+    bytes: Vec<u8>,
+    /// Whether `bytes` differ from the UTF-8 of the decoded path.
+    decoded: bool,
+}
+
+impl RawPath {
+    pub(crate) fn new(bytes: Vec<u8>, path: &str) -> Self {
+        let decoded = bytes != path.as_bytes();
+        Self { bytes, decoded }
+    }
+
+    #[allow(unused)]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn is_utf8_path(&self) -> bool {
+        !self.decoded
+    }
+}
+
+impl Serialize for RawPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.bytes.serialize(serializer)
+    }
+}
+
+/// FNV-1a of the path, in hex.
+fn path_id(path: &str) -> String {
+    let hash = path.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// Sizes of a file, or summed over all files below a folder.
+#[derive(Debug, Serialize, Clone, Copy, Type, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FsStats {
+    #[specta(type = f64)]
+    pub size: u64,
+    #[specta(type = f64)]
+    pub packed_size: u64,
+    #[specta(type = f64)]
+    pub files: u64,
+    /// `packed_size / size`, `None` if the size is 0.
+    pub ratio: Option<f64>,
+}
+
+impl FsStats {
+    /// Adds a file.
+    fn add(&mut self, entry: &ArchiveEntry) {
+        self.size += entry.size.unwrap_or_default();
+        self.packed_size += entry.packed_size.unwrap_or_default();
+        self.files += 1;
+        self.ratio = (self.size > 0).then(|| self.packed_size as f64 / self.size as f64);
+    }
 }
 
 impl fmt::Display for Fs {
@@ -45,6 +130,22 @@ impl FsNode {
         }
     }
 
+    fn stats_mut(&mut self) -> Option<&mut FsStats> {
+        match self {
+            Self::Dir(fs) | Self::File(fs) => Some(&mut fs.stats),
+            _ => None,
+        }
+    }
+
+    /// The `-slt` metadata of the entry, `None` for the root.
+    #[allow(unused)]
+    pub fn entry(&self) -> Option<&ArchiveEntry> {
+        match self {
+            Self::Dir(fs) | Self::File(fs) => Some(&fs.entry),
+            _ => None,
+        }
+    }
+
     #[allow(unused)]
     pub const fn is_none(&self) -> bool {
         matches!(self, Self::None)
@@ -72,49 +173,124 @@ impl fmt::Display for FsNode {
     }
 }
 
-#[derive(Debug, Serialize)]
-pub struct FsTree(Tree<FsNode>);
+#[derive(Debug)]
+pub struct FsTree {
+    tree: Tree<FsNode>,
+    /// The children of each node by name, so appending doesn't scan the siblings.
+    children: HashMap<NodeId, HashMap<String, NodeId>>,
+    /// Number of levels serialized below the root, all if `None`.
+    depth: Option<usize>,
+}
 
 impl FsTree {
+    fn new() -> Self {
+        Self {
+            tree: Tree::new(FsNode::None),
+            children: HashMap::new(),
+            depth: None,
+        }
+    }
+
+    /// Returns the node at `path` in the archive, the root if `path` is empty.
+    pub fn find(&self, path: &str) -> Option<NodeRef<'_, FsNode>> {
+        let mut node_id = self.tree.root().id();
+        for part in path.split(['\\', '/']).filter(|s| !s.is_empty()) {
+            node_id = *self.children.get(&node_id)?.get(part)?;
+        }
+        self.tree.get(node_id)
+    }
+
     fn append_file(&mut self, file: OutputFile) {
-        let tree = &mut *self;
-        let re = Regex::new(r"[\\/]").unwrap();
-        let mut sub_paths = re.split(&file.path).filter(|s| !s.is_empty()).peekable(); // NOTE: peekable
-        let mut parent = "";
-        let mut root = tree.root_mut();
-        let mut node_id = root.id();
-
-        'path: while let Some(part) = sub_paths.next() {
+        let mut sub_paths = file
+            .path
+            .split(['\\', '/'])
+            .filter(|s| !s.is_empty())
+            .peekable(); // NOTE: peekable
+        let mut path = String::new();
+        let mut node_id = self.tree.root().id();
+        // The nodes of the path, their sizes include the file.
+        let mut path_ids = Vec::new();
+        let raw_path = file
+            .raw_path
+            .clone()
+            .unwrap_or_else(|| file.path.clone().into_bytes());
+        let raw_parts = raw_path
+            .split(|&b| b == b'/' || b == b'\\')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let mut depth = 0;
+
+        while let Some(part) = sub_paths.next() {
+            let is_last = sub_paths.peek().is_none();
+            depth += 1;
+            let parent = path.clone();
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(part);
             // check if the part node already exists.
-            let node_ref = root.tree().get(node_id).unwrap();
-            for child in node_ref.children() {
-                if child.value().name() == part {
-                    node_id = child.id();
-                    parent = part;
-                    continue 'path;
+            let child_id = self
+                .children
+                .get(&node_id)
+                .and_then(|children| children.get(part));
+            if let Some(&child_id) = child_id {
+                node_id = child_id;
+                // the folder was created by a child listed before it.
+                if is_last
+                    && let FsNode::Dir(fs) | FsNode::File(fs) =
+                        self.tree.get_mut(node_id).unwrap().value()
+                {
+                    fs.modified = file.modified;
+                    fs.entry = file.entry.clone();
+                    fs.raw_path = RawPath::new(raw_path.clone(), &fs.path);
                 }
+                path_ids.push(node_id);
+                continue;
             }
             // create a new child node.
+            let raw_path = if is_last {
+                RawPath::new(raw_path.clone(), &path)
+            } else if sub_paths.clone().count() + depth == raw_parts.len() {
+                RawPath::new(raw_parts[..depth].join(&b'/'), &path)
+            } else {
+                // 解码前的分隔符对不上, 比如 Shift-JIS 的第二个字节是 `\`.
+                RawPath::new(path.clone().into_bytes(), &path)
+            };
             let fs = Fs {
                 name: part.to_string(),
-                parent: Some(parent.to_string()),
+                parent: Some(parent),
+                id: path_id(&path),
+                path: path.clone(),
+                raw_path,
                 modified: file.modified,
+                entry: if is_last {
+                    file.entry.clone()
+                } else {
+                    ArchiveEntry::default()
+                },
+                stats: FsStats::default(),
             };
-            parent = part;
-            let new_node = if sub_paths.peek().is_none() && !file.is_dir {
+            let new_node = if is_last && !file.is_dir {
                 FsNode::File(fs)
             } else {
                 FsNode::Dir(fs)
             };
-            let mut node = root.tree().get_mut(node_id).unwrap();
-            node_id = node.append(new_node).id();
+            let child_id = self.tree.get_mut(node_id).unwrap().append(new_node).id();
+            self.children
+                .entry(node_id)
+                .or_default()
+                .insert(part.to_string(), child_id);
+            node_id = child_id;
+            path_ids.push(node_id);
         }
-    }
-
-    fn sort(&mut self) {
-        let children = self.root().children().map(|n| n.id()).collect::<Vec<_>>();
 
-        sort_recursion(children, self);
+        if !file.is_dir {
+            for id in path_ids {
+                if let Some(stats) = self.tree.get_mut(id).unwrap().value().stats_mut() {
+                    stats.add(&file.entry);
+                }
+            }
+        }
     }
 
     fn only_one_root_dir(&self) -> bool {
@@ -127,20 +303,26 @@ impl FsTree {
     }
 }
 
-fn sort_recursion(children: Vec<NodeId>, tree: &mut Tree<FsNode>) {
-    for child in children {
-        {
-            let mut child_mut = tree.get_mut(child).unwrap();
-            child_mut.sort_by_key(|a| a.value().name());
-        }
-        let child_children = tree
-            .get(child)
-            .unwrap()
-            .children()
-            .map(|n| n.id())
-            .collect::<Vec<_>>();
-        sort_recursion(child_children, tree);
-    }
+/// Archive-level information, the header block of `7z l -slt` before the entries.
+#[derive(Debug, Serialize, Clone, Type, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveInfo {
+    /// The format, like `7z`, `zip` or `Rar5`.
+    pub archive_type: Option<String>,
+    #[specta(type = Option<f64>)]
+    pub physical_size: Option<u64>,
+    #[specta(type = Option<f64>)]
+    pub headers_size: Option<u64>,
+    pub method: Option<String>,
+    pub solid: bool,
+    #[specta(type = Option<f64>)]
+    pub blocks: Option<u64>,
+    /// File names can't be listed without the password.
+    pub encrypted_headers: bool,
+    pub comment: Option<String>,
+    pub recovery_record: bool,
+    /// RAR archives locked against changes.
+    pub locked: bool,
 }
 
 #[derive(Debug, Serialize, Type)]
@@ -153,24 +335,43 @@ pub struct ArchiveContents {
     codepage: OptionalCodepage,
     multi_volume: Option<ArchiveMultiVolume>,
     has_root_dir: bool,
+    info: ArchiveInfo,
+    /// Sizes of all files in the archive.
+    stats: FsStats,
+    /// Modified times of the entries, cached at once when the listing ends, see
+    /// [`ArchiveContents::take_modified`].
+    #[serde(skip)]
+    modified: FilesModified,
 }
 
 impl ArchiveContents {
     pub fn new(path: PathBuf) -> Self {
         ArchiveContents {
             path,
-            contents: FsTree(Tree::new(FsNode::None)),
+            contents: FsTree::new(),
             password: None,
             codepage: None,
             multi_volume: None,
             has_root_dir: false,
+            info: ArchiveInfo::default(),
+            stats: FsStats::default(),
+            modified: FilesModified::new(),
         }
     }
 
     pub fn append_file(&mut self, file: OutputFile) {
+        self.modified.insert(file.path.clone(), file.modified);
+        if !file.is_dir {
+            self.stats.add(&file.entry);
+        }
         self.contents.append_file(file);
     }
 
+    /// Takes the modified times of the entries appended so far, by path in the archive.
+    pub fn take_modified(&mut self) -> FilesModified {
+        std::mem::take(&mut self.modified)
+    }
+
     pub fn set_has_root_dir(&mut self) -> bool {
         self.has_root_dir = self.contents.only_one_root_dir();
         let mut map = archives_have_root_dir();
@@ -187,12 +388,40 @@ impl ArchiveContents {
         }
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Size of the files at `path`, of the whole archive if `path` is empty.
+    ///
+    /// `None` if there is no such entry.
+    pub fn size_of(&self, path: &str) -> Option<u64> {
+        if path.is_empty() {
+            return Some(self.stats.size);
+        }
+        match self.contents.find(path)?.value() {
+            FsNode::Dir(fs) => Some(fs.stats.size),
+            FsNode::File(fs) => Some(fs.entry.size.unwrap_or_default()),
+            FsNode::None => None,
+        }
+    }
+
+    /// Sends only `depth` levels of the tree to the frontend, the rest is loaded with
+    /// `get_archive_children`.
+    pub fn set_depth(&mut self, depth: Option<usize>) {
+        self.contents.depth = depth;
+    }
+
+    pub fn set_info(&mut self, info: ArchiveInfo) {
+        self.info = info;
+    }
+
     pub fn set_codepage(&mut self, codepage: OptionalCodepage) {
         self.codepage = codepage;
     }
 
-    pub fn sort(&mut self) {
-        self.contents.sort();
+    pub fn sort(&mut self, options: &SortOptions) {
+        self.contents.sort(options);
     }
 
     /// Whether the archive is a multi-volume archive.
@@ -218,17 +447,22 @@ impl Deref for FsTree {
     type Target = Tree<FsNode>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.tree
     }
 }
 
 impl DerefMut for FsTree {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.tree
     }
 }
 
+pub mod diff;
 mod display;
+pub mod export;
+pub mod extracted;
+pub mod search;
+pub mod sort;
 
 impl fmt::Display for FsTree {
     // Doesn't display the root node.
@@ -267,11 +501,83 @@ impl fmt::Display for ArchiveContents {
     }
 }
 
+impl Serialize for FsTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerNode {
+            node: self.root(),
+            depth: self.depth,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// A node of a listed archive with `depth` levels of children, serialized like [`SpectaNode`].
+pub struct FsSubtree {
+    contents: Arc<ArchiveContents>,
+    node_id: NodeId,
+    depth: Option<usize>,
+}
+
+impl FsSubtree {
+    /// Returns `None` if there is no node at `path`, see [`FsTree::find`].
+    pub fn new(contents: Arc<ArchiveContents>, path: &str, depth: Option<usize>) -> Option<Self> {
+        let node_id = contents.contents.find(path)?.id();
+        Some(Self {
+            contents,
+            node_id,
+            depth,
+        })
+    }
+}
+
+impl Serialize for FsSubtree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerNode {
+            node: self.contents.contents.get(self.node_id).unwrap(),
+            depth: self.depth,
+        }
+        .serialize(serializer)
+    }
+}
+
+struct SerNode<'a> {
+    node: NodeRef<'a, FsNode>,
+    /// Levels of children left, `Some(0)` leaves them out.
+    depth: Option<usize>,
+}
+
+impl Serialize for SerNode<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let truncated = self.depth == Some(0) && self.node.has_children();
+        let mut state = serializer.serialize_struct("Node", 3)?;
+        state.serialize_field("value", self.node.value())?;
+        if truncated {
+            state.serialize_field("children", &[] as &[SerNode])?;
+        } else {
+            state.serialize_field("children", &SerChildren(self))?;
+        }
+        state.serialize_field("truncated", &truncated)?;
+        state.end()
+    }
+}
+
+/// Serializes the children without collecting them.
+struct SerChildren<'a, 'b>(&'b SerNode<'a>);
+
+impl Serialize for SerChildren<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let depth = self.0.depth.map(|depth| depth.saturating_sub(1));
+        serializer.collect_seq(self.0.node.children().map(|node| SerNode { node, depth }))
+    }
+}
+
 // TODO: https://github.com/specta-rs/specta/issues/285 Recursive structs cause a stack overflow
 #[derive(Serialize, Type)]
 pub struct SpectaNode<'a, T> {
     value: &'a T,
     children: Vec<SpectaNode<'a, T>>,
+    /// The children were not sent, see [`ArchiveContents::set_depth`].
+    truncated: bool,
 }
 
 impl Type for FsTree {
@@ -309,37 +615,346 @@ impl NamedType for FsTree {
     }
 }
 
+impl Type for FsSubtree {
+    fn inline(
+        type_map: &mut specta::TypeMap,
+        generics: specta::Generics,
+    ) -> specta::datatype::DataType {
+        FsTree::inline(type_map, generics)
+    }
+
+    fn reference(
+        type_map: &mut specta::TypeMap,
+        generics: &[specta::datatype::DataType],
+    ) -> specta::datatype::reference::Reference {
+        specta::datatype::reference::inline::<Self>(type_map, generics)
+    }
+}
+
 #[cfg(test)]
 mod test_fs_tree {
     use super::*;
 
     #[test]
     fn new() {
-        let tree = FsTree(Tree::new(FsNode::None));
+        let tree = FsTree::new();
         assert_eq!(format!("{}", tree), "");
     }
 
     #[test]
     fn append_path() {
-        let mut tree = FsTree(Tree::new(FsNode::None));
+        let mut tree = FsTree::new();
         tree.append_file(OutputFile {
             path: "a\\b\\c".to_string(),
+            raw_path: None,
             is_dir: true,
             modified: None,
+            entry: ArchiveEntry::default(),
         });
         tree.append_file(OutputFile {
             path: "a\\b\\d".to_string(),
+            raw_path: None,
             is_dir: false,
             modified: None,
+            entry: ArchiveEntry::default(),
         });
         tree.append_file(OutputFile {
             path: "a\\e\\f".to_string(),
+            raw_path: None,
             is_dir: true,
             modified: None,
+            entry: ArchiveEntry::default(),
         });
+        // FsNode 的 Display 以 📁 或 📄 开头.
+        assert_eq!(
+            format!("{}", tree),
+            "📁 a\n├── 📁 b\n│   ├── 📁 c\n│   └── 📄 d\n└── 📁 e\n    └── 📁 f\n"
+        );
+    }
+
+    #[test]
+    fn full_paths() {
+        let mut tree = FsTree::new();
+        let files = [
+            ("a/x/1", None),
+            ("b/x", Some(b"b\\x".to_vec())),
+            ("日/本", Some(b"\x93\xfa/\x96\x7b".to_vec())),
+        ];
+        for (path, raw_path) in files {
+            tree.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path,
+                is_dir: true,
+                modified: None,
+                entry: ArchiveEntry::default(),
+            });
+        }
+        let fs = |path| match tree.find(path).unwrap().value() {
+            FsNode::Dir(fs) | FsNode::File(fs) => fs.clone(),
+            FsNode::None => unreachable!(),
+        };
+        let (ax, bx) = (fs("a/x"), fs("b/x"));
+        assert_eq!((ax.path.as_str(), ax.parent.as_deref()), ("a/x", Some("a")));
+        assert_eq!((bx.path.as_str(), bx.parent.as_deref()), ("b/x", Some("b")));
+        assert_ne!(ax.id, bx.id);
+        assert_eq!(ax.id, path_id("a/x"));
+        // 7z 的路径和只由子项隐含的文件夹也有原始路径.
+        assert_eq!(ax.raw_path.as_bytes(), b"a/x");
+        assert!(ax.raw_path.is_utf8_path());
+        assert_eq!(bx.raw_path.as_bytes(), b"b\\x");
+        assert_eq!(fs("b").raw_path.as_bytes(), b"b");
+        assert_eq!(fs("日").raw_path.as_bytes(), b"\x93\xfa");
+        assert!(!fs("日").raw_path.is_utf8_path());
+        assert_eq!(fs("a/x/1").parent.as_deref(), Some("a/x"));
+        assert_eq!(fs("a").parent.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn sort_then_append() {
+        let file = |path: &str| OutputFile {
+            path: path.to_string(),
+            raw_path: None,
+            is_dir: false,
+            modified: None,
+            entry: ArchiveEntry::default(),
+        };
+        let mut tree = FsTree::new();
+        for path in ["c/z", "b", "c/x", "a"] {
+            tree.append_file(file(path));
+        }
+        tree.sort(&SortOptions::default());
+        // the index still points to the moved nodes.
+        tree.append_file(file("c/y"));
+        tree.append_file(file("c/x"));
+        tree.sort(&SortOptions::default());
         assert_eq!(
             format!("{}", tree),
-            "a\n├── b\n│   ├── c\n│   └── d\n└── e\n    └── f\n"
+            "📄 a
+📄 b
+📁 c
+├── 📄 x
+├── 📄 y
+└── 📄 z
+"
+        );
+    }
+
+    #[test]
+    fn serialize_depth() {
+        let mut archive = ArchiveContents::new(PathBuf::from("test.7z"));
+        for path in ["a/b/c.txt", "a/d.txt"] {
+            archive.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir: false,
+                modified: None,
+                entry: ArchiveEntry::default(),
+            });
+        }
+        archive.set_depth(Some(1));
+        let json = serde_json::to_value(&archive.contents).unwrap();
+        let a = &json["children"][0];
+        assert_eq!(a["value"]["name"], "a");
+        assert_eq!(a["truncated"], true);
+        assert_eq!(a["children"], serde_json::json!([]));
+
+        let archive = Arc::new(archive);
+        let subtree = FsSubtree::new(archive.clone(), "a", Some(1)).unwrap();
+        let json = serde_json::to_value(&subtree).unwrap();
+        assert_eq!(json["value"]["name"], "a");
+        assert_eq!(json["children"][0]["value"]["name"], "b");
+        assert_eq!(json["children"][0]["truncated"], true);
+        assert_eq!(json["children"][1]["truncated"], false);
+        let json = serde_json::to_value(FsSubtree::new(archive.clone(), "a\\b", None)).unwrap();
+        assert_eq!(json["children"][0]["value"]["name"], "c.txt");
+        assert!(FsSubtree::new(archive, "a/x", None).is_none());
+    }
+
+    #[test]
+    fn stats() {
+        let mut archive = ArchiveContents::new(PathBuf::from("test.7z"));
+        for (path, is_dir, size, packed_size) in [
+            ("a/b/c.txt", false, 100, 40),
+            ("a/b", true, 0, 0),
+            ("a/d.txt", false, 300, 0),
+            ("e.txt", false, 0, 0),
+        ] {
+            archive.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir,
+                modified: None,
+                entry: ArchiveEntry {
+                    size: Some(size),
+                    packed_size: Some(packed_size),
+                    ..Default::default()
+                },
+            });
+        }
+        let stats = |node: ego_tree::NodeRef<'_, FsNode>| match node.value() {
+            FsNode::Dir(fs) | FsNode::File(fs) => fs.stats,
+            FsNode::None => unreachable!(),
+        };
+
+        let a = archive.contents.root().first_child().unwrap();
+        assert_eq!(
+            stats(a),
+            FsStats {
+                size: 400,
+                packed_size: 40,
+                files: 2,
+                ratio: Some(0.1),
+            }
+        );
+        let b = a.first_child().unwrap();
+        assert_eq!((stats(b).size, stats(b).files), (100, 1));
+        assert_eq!(stats(b.first_child().unwrap()).ratio, Some(0.4));
+        let e = a.next_sibling().unwrap();
+        assert_eq!(stats(e).ratio, None);
+        assert_eq!((archive.stats.size, archive.stats.files), (400, 3));
+    }
+
+    const LIST_OUTPUT: &str = "
+7-Zip (z) 24.08 (x64) : Copyright (c) 1999-2024 Igor Pavlov : 2024-08-11
+
+Listing archive: test.7z
+
+--
+Path = test.7z
+Type = 7z
+Physical Size = 300
+Headers Size = 200
+Method = LZMA2:12 7zAES
+Solid = +
+Blocks = 1
+Comment = first line
+second line
+
+----------
+Path = root/a.txt
+Size = 5
+Packed Size = 16
+Modified = 2024-01-02 03:04:05.1234567
+Created = 
+Accessed = 
+Attributes = A -rw-r--r--
+CRC = 3610A686
+Encrypted = +
+Method = LZMA2:12 7zAES:19
+Block = 0
+
+Path = root
+Size = 0
+Packed Size = 0
+Modified = 2024-01-02 03:04:05
+Attributes = D drwxr-xr-x
+CRC = 
+Encrypted = -
+Method = 
+Block = 
+";
+
+    #[test]
+    fn parse_entries() {
+        let archive = crate::sevenz::parse_list_output("test.7z", LIST_OUTPUT).unwrap();
+        assert_eq!(archive.to_string(), "test.7z\n📁 root\n└── 📄 a.txt\n\n");
+
+        assert_eq!(
+            archive.info,
+            ArchiveInfo {
+                archive_type: Some("7z".to_string()),
+                physical_size: Some(300),
+                headers_size: Some(200),
+                method: Some("LZMA2:12 7zAES".to_string()),
+                solid: true,
+                blocks: Some(1),
+                comment: Some("first line\nsecond line".to_string()),
+                ..Default::default()
+            }
         );
+
+        let root = archive.contents.root().first_child().unwrap();
+        let entry = root.value().entry().unwrap();
+        assert_eq!(entry.attributes.as_deref(), Some("D drwxr-xr-x"));
+        assert_eq!(entry.crc, None);
+
+        let entry = root.first_child().unwrap().value().entry().unwrap().clone();
+        assert_eq!(
+            entry,
+            ArchiveEntry {
+                size: Some(5),
+                packed_size: Some(16),
+                crc: Some("3610A686".to_string()),
+                method: Some("LZMA2:12 7zAES:19".to_string()),
+                encrypted: true,
+                attributes: Some("A -rw-r--r--".to_string()),
+                block: Some(0),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_streaming() {
+        let entries = std::cell::RefCell::new(Vec::new());
+        let on_entries = |count| entries.borrow_mut().push(count);
+        let mut parser = crate::sevenz::ListParser::new("big.7z".as_ref(), &on_entries);
+        for line in LIST_OUTPUT.lines().take_while(|line| *line != "----------") {
+            parser.push_line(line).unwrap();
+        }
+        parser.push_line("----------").unwrap();
+        for i in 0..2_500 {
+            parser.push_line(&format!("Path = dir/{i}.txt")).unwrap();
+            parser.push_line("Size = 10").unwrap();
+            parser.push_line("").unwrap();
+        }
+        // the last entry has no empty line after it.
+        parser.push_line("Path = last.txt").unwrap();
+        assert_eq!(*entries.borrow(), [1_000, 2_000]);
+
+        let archive = parser.finish();
+        assert_eq!(archive.stats.files, 2_501);
+        assert_eq!(archive.stats.size, 25_000);
+        assert_eq!(archive.info.archive_type.as_deref(), Some("7z"));
+        let dir = archive.contents.root().first_child().unwrap();
+        assert_eq!(dir.children().count(), 2_500);
+    }
+
+    /// Builds trees from generated listings in reverse order, see `benches/fs_tree.rs` for the
+    /// timings with 1M entries.
+    #[test]
+    fn generated_listings() {
+        use std::fmt::Write;
+
+        fn flat(i: usize) -> String {
+            format!("dir/{i:07}.txt")
+        }
+        fn nested(i: usize) -> String {
+            format!("{}/{}/{i:07}.txt", i % 100, i / 100 % 100)
+        }
+
+        let count = 20_000;
+        for (layout, path) in [("flat", flat as fn(usize) -> String), ("nested", nested)] {
+            let mut output = String::from("Type = 7z\n\n----------\n");
+            for i in (0..count).rev() {
+                write!(output, "Path = {}\nSize = 1\nAttributes = A\n\n", path(i)).unwrap();
+            }
+            let mut archive = crate::sevenz::parse_list_output("bench.7z", &output).unwrap();
+            archive.sort(&SortOptions::default());
+
+            assert_eq!(archive.stats.files, count as u64);
+            let leaves = archive
+                .contents
+                .root()
+                .descendants()
+                .filter(|node| node.value().is_file())
+                .map(|node| node.value().name())
+                .collect::<Vec<_>>();
+            assert_eq!(leaves.len(), count);
+            if layout == "flat" {
+                assert!(leaves.is_sorted());
+            }
+            assert_eq!(archive.take_modified().len(), count);
+        }
     }
 }
@@ -0,0 +1,308 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use specta::Type;
+use time::OffsetDateTime;
+
+use super::{ArchiveContents, Fs, FsNode};
+
+/// A metadata of an entry that differs between the archives.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Type)]
+pub enum DiffField {
+    Size,
+    Crc,
+    Modified,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(tag = "type")]
+pub enum DiffStatus {
+    /// Only in the new archive.
+    Added,
+    /// Only in the old archive.
+    Removed,
+    /// In both archives with different metadata.
+    Modified { fields: Vec<DiffField> },
+    /// The same file under another path, `from` is the path in the old archive.
+    Moved { from: String },
+}
+
+/// A node of the diff tree.
+///
+/// Only the changed entries and the folders containing them are in the tree,
+/// `status` is `None` for the unchanged folders.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffNode {
+    pub name: String,
+    /// The full path in the archive, separated by `/`.
+    pub path: String,
+    pub is_dir: bool,
+    pub status: Option<DiffStatus>,
+    pub children: Vec<DiffNode>,
+}
+
+/// The changes from an old archive to a new one, see [`ArchiveContents::diff`].
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct ArchiveDiff {
+    pub added: u32,
+    pub removed: u32,
+    pub modified: u32,
+    pub moved: u32,
+    /// The top level nodes.
+    pub tree: Vec<DiffNode>,
+}
+
+/// Files with the same key are the same file, moved if the paths differ.
+#[derive(PartialEq, Eq, Hash)]
+enum MoveKey<'a> {
+    Crc(Option<u64>, &'a str),
+    /// Without CRC, the name must be the same too.
+    Modified(Option<u64>, OffsetDateTime, &'a str),
+}
+
+impl<'a> MoveKey<'a> {
+    fn new(fs: &'a Fs) -> Option<Self> {
+        match (&fs.entry.crc, fs.modified) {
+            (Some(crc), _) => Some(Self::Crc(fs.entry.size, crc)),
+            (None, Some(modified)) => Some(Self::Modified(fs.entry.size, modified, &fs.name)),
+            (None, None) => None,
+        }
+    }
+}
+
+impl ArchiveContents {
+    /// Compares the entries by path, size, CRC and modified time.
+    ///
+    /// A file removed from one path and added to another with the same size and CRC,
+    /// or the same size, modified time and name if there is no CRC, is moved.
+    pub fn diff(&self, new: &ArchiveContents) -> ArchiveDiff {
+        let old_entries = self.entries();
+        let new_entries = new.entries();
+        let mut changes = Vec::new();
+
+        let mut removed: HashMap<MoveKey, Vec<&str>> = HashMap::new();
+        for (path, (fs, is_dir)) in &old_entries {
+            match new_entries.get(path) {
+                Some((new_fs, new_is_dir)) if is_dir == new_is_dir => {
+                    let fields = changed_fields(fs, new_fs);
+                    if !*is_dir && !fields.is_empty() {
+                        changes.push((*path, false, DiffStatus::Modified { fields }));
+                    }
+                }
+                _ => match MoveKey::new(fs).filter(|_| !is_dir) {
+                    Some(key) => removed.entry(key).or_default().push(path),
+                    None => changes.push((*path, *is_dir, DiffStatus::Removed)),
+                },
+            }
+        }
+        // 倒序, 配对时从头取.
+        removed.values_mut().for_each(|paths| paths.reverse());
+
+        for (path, (fs, is_dir)) in &new_entries {
+            if old_entries
+                .get(path)
+                .is_some_and(|(_, old_is_dir)| old_is_dir == is_dir)
+            {
+                continue;
+            }
+            let from = MoveKey::new(fs)
+                .filter(|_| !is_dir)
+                .and_then(|key| removed.get_mut(&key))
+                .and_then(|paths| paths.pop());
+            let status = match from {
+                Some(from) => DiffStatus::Moved {
+                    from: from.to_string(),
+                },
+                None => DiffStatus::Added,
+            };
+            changes.push((*path, *is_dir, status));
+        }
+        for path in removed.into_values().flatten() {
+            changes.push((path, false, DiffStatus::Removed));
+        }
+
+        let mut diff = ArchiveDiff {
+            added: 0,
+            removed: 0,
+            modified: 0,
+            moved: 0,
+            tree: Vec::new(),
+        };
+        let mut root = DiffBuilder::default();
+        for (path, is_dir, status) in changes {
+            match status {
+                DiffStatus::Added => diff.added += 1,
+                DiffStatus::Removed => diff.removed += 1,
+                DiffStatus::Modified { .. } => diff.modified += 1,
+                DiffStatus::Moved { .. } => diff.moved += 1,
+            }
+            root.insert(path, is_dir, status);
+        }
+        diff.tree = root.build("");
+        diff
+    }
+
+    /// All the entries by path.
+    fn entries(&self) -> BTreeMap<&str, (&Fs, bool)> {
+        self.contents
+            .root()
+            .descendants()
+            .filter_map(|node| match node.value() {
+                FsNode::Dir(fs) => Some((fs.path.as_str(), (fs, true))),
+                FsNode::File(fs) => Some((fs.path.as_str(), (fs, false))),
+                FsNode::None => None,
+            })
+            .collect()
+    }
+}
+
+/// The fields known in both archives and different.
+fn changed_fields(old: &Fs, new: &Fs) -> Vec<DiffField> {
+    fn differ<T: PartialEq>(old: &Option<T>, new: &Option<T>) -> bool {
+        matches!((old, new), (Some(old), Some(new)) if old != new)
+    }
+    let mut fields = Vec::new();
+    if differ(&old.entry.size, &new.entry.size) {
+        fields.push(DiffField::Size);
+    }
+    if differ(&old.entry.crc, &new.entry.crc) {
+        fields.push(DiffField::Crc);
+    }
+    if differ(&old.modified, &new.modified) {
+        fields.push(DiffField::Modified);
+    }
+    fields
+}
+
+#[derive(Default)]
+struct DiffBuilder {
+    is_dir: bool,
+    status: Option<DiffStatus>,
+    children: BTreeMap<String, DiffBuilder>,
+}
+
+impl DiffBuilder {
+    fn insert(&mut self, path: &str, is_dir: bool, status: DiffStatus) {
+        let mut node = self;
+        for name in path.split('/') {
+            node.is_dir = true;
+            node = node.children.entry(name.to_string()).or_default();
+        }
+        node.is_dir |= is_dir;
+        node.status = Some(status);
+    }
+
+    fn build(self, parent: &str) -> Vec<DiffNode> {
+        self.children
+            .into_iter()
+            .map(|(name, node)| {
+                let path = match parent {
+                    "" => name.clone(),
+                    _ => format!("{parent}/{name}"),
+                };
+                let DiffBuilder {
+                    is_dir,
+                    status,
+                    children,
+                } = node;
+                let children = DiffBuilder {
+                    children,
+                    ..Default::default()
+                }
+                .build(&path);
+                DiffNode {
+                    name,
+                    path,
+                    is_dir,
+                    status,
+                    children,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_diff {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::sevenz::{entry::ArchiveEntry, OutputFile};
+
+    fn archive(entries: &[(&str, bool, u64, &str)]) -> ArchiveContents {
+        let mut archive = ArchiveContents::new(PathBuf::from("test.7z"));
+        for &(path, is_dir, size, crc) in entries {
+            archive.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir,
+                modified: None,
+                entry: ArchiveEntry {
+                    size: Some(size),
+                    crc: (!crc.is_empty()).then(|| crc.to_string()),
+                    ..Default::default()
+                },
+            });
+        }
+        archive
+    }
+
+    #[test]
+    fn diff() {
+        let old = archive(&[
+            ("docs", true, 0, ""),
+            ("docs/readme.md", false, 10, "AAAA0001"),
+            ("docs/old.md", false, 20, "AAAA0002"),
+            ("src/main.rs", false, 100, "AAAA0003"),
+            ("logo.png", false, 5_000, "AAAA0004"),
+        ]);
+        let new = archive(&[
+            ("docs", true, 0, ""),
+            ("docs/readme.md", false, 12, "BBBB0001"),
+            ("src/main.rs", false, 100, "AAAA0003"),
+            ("assets/logo.png", false, 5_000, "AAAA0004"),
+            ("assets/icon.svg", false, 300, "BBBB0005"),
+        ]);
+        let diff = old.diff(&new);
+        assert_eq!(
+            (diff.added, diff.removed, diff.modified, diff.moved),
+            (2, 1, 1, 1)
+        );
+
+        let flat = |tree: &[DiffNode]| {
+            fn walk(nodes: &[DiffNode], flat: &mut Vec<(String, Option<DiffStatus>)>) {
+                for node in nodes {
+                    flat.push((node.path.clone(), node.status.clone()));
+                    walk(&node.children, flat);
+                }
+            }
+            let mut flat = Vec::new();
+            walk(tree, &mut flat);
+            flat
+        };
+        assert_eq!(
+            flat(&diff.tree),
+            vec![
+                ("assets".to_string(), Some(DiffStatus::Added)),
+                ("assets/icon.svg".to_string(), Some(DiffStatus::Added)),
+                (
+                    "assets/logo.png".to_string(),
+                    Some(DiffStatus::Moved {
+                        from: "logo.png".to_string()
+                    })
+                ),
+                ("docs".to_string(), None),
+                ("docs/old.md".to_string(), Some(DiffStatus::Removed)),
+                (
+                    "docs/readme.md".to_string(),
+                    Some(DiffStatus::Modified {
+                        fields: vec![DiffField::Size, DiffField::Crc]
+                    })
+                ),
+            ]
+        );
+        assert!(diff.tree[0].is_dir);
+        assert_eq!(old.diff(&old).tree, vec![]);
+    }
+}
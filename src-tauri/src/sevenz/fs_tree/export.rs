@@ -0,0 +1,246 @@
+use std::{fs, path::Path, sync::Arc};
+
+use ego_tree::iter::Edge;
+use serde::{Deserialize, Serialize, Serializer};
+use specta::Type;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::{ArchiveContents, ArchiveInfo, FsNode, FsStats, SerNode};
+use crate::sevenz::error::SevenzError;
+
+/// The file format of [`export_listing`].
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Type)]
+pub enum ExportFormat {
+    /// The trees with all metadata.
+    Json,
+    /// One row per entry.
+    Csv,
+    /// The trees as printed by [`super::FsTree`]'s `Display`, readable as plain text too.
+    Markdown,
+    /// A static page with collapsible folders.
+    Html,
+}
+
+/// Writes the listings of the archives to `output`.
+pub fn export_listing(
+    listings: &[Arc<ArchiveContents>],
+    format: ExportFormat,
+    output: &Path,
+) -> Result<(), SevenzError> {
+    let text = match format {
+        ExportFormat::Json => to_json(listings),
+        ExportFormat::Csv => to_csv(listings),
+        ExportFormat::Markdown => to_markdown(listings),
+        ExportFormat::Html => to_html(listings),
+    };
+    fs::write(output, text)?;
+    Ok(())
+}
+
+/// An archive in the JSON export, without the password.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonArchive<'a> {
+    path: &'a Path,
+    info: &'a ArchiveInfo,
+    stats: &'a FsStats,
+    #[serde(serialize_with = "full_tree")]
+    contents: &'a ArchiveContents,
+}
+
+/// The whole tree, even if the listing is sent by depth.
+fn full_tree<S: Serializer>(contents: &&ArchiveContents, serializer: S) -> Result<S::Ok, S::Error> {
+    SerNode {
+        node: contents.contents.root(),
+        depth: None,
+    }
+    .serialize(serializer)
+}
+
+fn to_json(listings: &[Arc<ArchiveContents>]) -> String {
+    let archives = listings
+        .iter()
+        .map(|contents| JsonArchive {
+            path: &contents.path,
+            info: &contents.info,
+            stats: &contents.stats,
+            contents,
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&archives).unwrap()
+}
+
+const CSV_HEADER: &str =
+    "archive,path,type,size,packed_size,modified,crc,method,encrypted,attributes,comment";
+
+fn to_csv(listings: &[Arc<ArchiveContents>]) -> String {
+    let mut csv = format!("{CSV_HEADER}\r\n");
+    for contents in listings {
+        let archive = contents.path.to_string_lossy();
+        for node in contents.contents.root().descendants() {
+            let (kind, fs) = match node.value() {
+                FsNode::Dir(fs) => ("dir", fs),
+                FsNode::File(fs) => ("file", fs),
+                FsNode::None => continue,
+            };
+            let (size, packed_size) = match kind {
+                "dir" => (Some(fs.stats.size), Some(fs.stats.packed_size)),
+                _ => (fs.entry.size, fs.entry.packed_size),
+            };
+            let entry = &fs.entry;
+            let row = [
+                archive.to_string(),
+                fs.path.clone(),
+                kind.to_string(),
+                size.map(|size| size.to_string()).unwrap_or_default(),
+                packed_size.map(|size| size.to_string()).unwrap_or_default(),
+                format_datetime(fs.modified),
+                entry.crc.clone().unwrap_or_default(),
+                entry.method.clone().unwrap_or_default(),
+                entry.encrypted.to_string(),
+                entry.attributes.clone().unwrap_or_default(),
+                entry.comment.clone().unwrap_or_default(),
+            ];
+            let row = row.iter().map(|field| csv_field(field)).collect::<Vec<_>>();
+            csv.push_str(&row.join(","));
+            csv.push_str("\r\n");
+        }
+    }
+    csv
+}
+
+fn format_datetime(datetime: Option<OffsetDateTime>) -> String {
+    datetime
+        .and_then(|datetime| datetime.format(&Rfc3339).ok())
+        .unwrap_or_default()
+}
+
+/// Quotes the field if needed, see RFC 4180.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_markdown(listings: &[Arc<ArchiveContents>]) -> String {
+    listings
+        .iter()
+        .map(|contents| {
+            format!(
+                "## {}\n\n```text\n{}```\n",
+                contents.path.display(),
+                contents.contents
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn to_html(listings: &[Arc<ArchiveContents>]) -> String {
+    let mut html = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>e-zip</title>\n",
+        "<style>body{font-family:sans-serif}ul{list-style:none;padding-left:1.2em}",
+        ".size{color:gray;margin-left:1em}</style>\n</head>\n<body>\n",
+    ));
+    for contents in listings {
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<ul>\n",
+            escape_html(&contents.path.to_string_lossy())
+        ));
+        for edge in contents.contents.root().traverse() {
+            match edge {
+                Edge::Open(node) => match node.value() {
+                    FsNode::Dir(fs) => html.push_str(&format!(
+                        "<li><details><summary>📁 {}<span class=\"size\">{}</span></summary>\n<ul>\n",
+                        escape_html(&fs.name),
+                        fs.stats.size
+                    )),
+                    FsNode::File(fs) => html.push_str(&format!(
+                        "<li>📄 {}<span class=\"size\">{}</span></li>\n",
+                        escape_html(&fs.name),
+                        fs.entry.size.map(|size| size.to_string()).unwrap_or_default()
+                    )),
+                    FsNode::None => {}
+                },
+                Edge::Close(node) if node.value().is_dir() => {
+                    html.push_str("</ul>\n</details></li>\n");
+                }
+                Edge::Close(_) => {}
+            }
+        }
+        html.push_str("</ul>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test_export {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::sevenz::{entry::ArchiveEntry, OutputFile};
+
+    #[test]
+    fn export() {
+        let mut archive = ArchiveContents::new(PathBuf::from("delivery.7z"));
+        for (path, is_dir, comment) in [
+            ("docs", true, None),
+            ("docs/a, \"b\".txt", false, Some("line\nbreak")),
+            ("<x>.md", false, None),
+        ] {
+            archive.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir,
+                modified: None,
+                entry: ArchiveEntry {
+                    size: Some(3),
+                    comment: comment.map(str::to_owned),
+                    ..Default::default()
+                },
+            });
+        }
+        archive.set_password("secret");
+        archive.set_depth(Some(0));
+        let listings = [Arc::new(archive)];
+
+        let json = to_json(&listings);
+        assert!(!json.contains("secret"));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value[0]["contents"]["children"][0]["children"][0]["value"]["path"],
+            "docs/a, \"b\".txt"
+        );
+
+        let csv = to_csv(&listings);
+        let lines = csv.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "delivery.7z,docs,dir,3,0,,,,false,,");
+        assert_eq!(
+            lines[2],
+            "delivery.7z,\"docs/a, \"\"b\"\".txt\",file,3,,,,,false,,\"line\nbreak\""
+        );
+
+        assert_eq!(
+            to_markdown(&listings),
+            "## delivery.7z\n\n```text\n📁 docs\n└── 📄 a, \"b\".txt\n📄 <x>.md\n```\n"
+        );
+
+        let html = to_html(&listings);
+        assert!(html.contains("<li>📄 &lt;x&gt;.md<span class=\"size\">3</span></li>"));
+        assert_eq!(
+            html.matches("<details>").count(),
+            html.matches("</details>").count()
+        );
+    }
+}
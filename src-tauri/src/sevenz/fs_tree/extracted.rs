@@ -0,0 +1,197 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use time::OffsetDateTime;
+
+use super::{ArchiveContents, Fs, FsNode};
+use crate::sevenz::unzip::target_base_dir;
+
+/// How files on disk are compared to the entries, each includes the previous.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Type)]
+pub enum CompareBy {
+    Size,
+    Modified,
+    /// CRC32, reads the files.
+    Hash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Type)]
+pub enum ExtractedStatus {
+    Identical,
+    Different,
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+pub struct ExtractedEntry {
+    /// The full path in the archive, separated by `/`.
+    pub path: String,
+    pub status: ExtractedStatus,
+}
+
+/// The files of an archive found in the target folder, see [`ArchiveContents::check_extracted`].
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedReport {
+    pub archive: PathBuf,
+    /// Where the files of the archive would be extracted to.
+    pub output: PathBuf,
+    pub identical: u32,
+    pub different: u32,
+    pub missing: u32,
+    /// The files in tree order, folders are not checked.
+    pub entries: Vec<ExtractedEntry>,
+}
+
+impl ExtractedReport {
+    /// All files are extracted and identical, the archive can be skipped.
+    #[allow(unused)]
+    pub fn is_extracted(&self) -> bool {
+        self.different == 0 && self.missing == 0
+    }
+}
+
+/// Zip stores the modified time in 2 seconds.
+const MODIFIED_TOLERANCE: i64 = 2;
+
+impl ArchiveContents {
+    /// Checks the files of the archive against the folder it would be extracted to.
+    ///
+    /// The output folder follows [`crate::sevenz::unzip::unzip_selected`]: `target_dir`, or the folder of
+    /// the archive if empty, and a folder named after the archive if it has no root folder.
+    pub fn check_extracted(&self, target_dir: &Path, compare: CompareBy) -> ExtractedReport {
+        let mut output = target_base_dir(&self.path, target_dir);
+        if !self.has_root_dir {
+            output.push(self.path.file_stem().unwrap_or_default());
+        }
+
+        let mut report = ExtractedReport {
+            archive: self.path.clone(),
+            output,
+            identical: 0,
+            different: 0,
+            missing: 0,
+            entries: Vec::new(),
+        };
+        for node in self.contents.root().descendants() {
+            let FsNode::File(fs) = node.value() else {
+                continue;
+            };
+            let status = extracted_status(fs, &report.output.join(&fs.path), compare);
+            match status {
+                ExtractedStatus::Identical => report.identical += 1,
+                ExtractedStatus::Different => report.different += 1,
+                ExtractedStatus::Missing => report.missing += 1,
+            }
+            report.entries.push(ExtractedEntry {
+                path: fs.path.clone(),
+                status,
+            });
+        }
+        report
+    }
+}
+
+/// Metadata the archive doesn't store is considered identical.
+fn extracted_status(fs: &Fs, path: &Path, compare: CompareBy) -> ExtractedStatus {
+    let Ok(metadata) = fs::metadata(path) else {
+        return ExtractedStatus::Missing;
+    };
+    if !metadata.is_file() {
+        return ExtractedStatus::Different;
+    }
+    let identical = fs.entry.size.is_none_or(|size| size == metadata.len())
+        && (compare < CompareBy::Modified
+            || fs.modified.is_none_or(|modified| {
+                metadata.modified().is_ok_and(|actual| {
+                    let actual = OffsetDateTime::from(actual).unix_timestamp();
+                    (actual - modified.unix_timestamp()).abs() <= MODIFIED_TOLERANCE
+                })
+            }))
+        && (compare < CompareBy::Hash
+            || fs.entry.crc.as_ref().is_none_or(|crc| {
+                file_crc(path).is_ok_and(|actual| crc.eq_ignore_ascii_case(&actual))
+            }));
+    if identical {
+        ExtractedStatus::Identical
+    } else {
+        ExtractedStatus::Different
+    }
+}
+
+/// CRC32 in upper case hex, like 7z prints.
+fn file_crc(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(format!("{:08X}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod test_extracted {
+    use super::*;
+    use crate::sevenz::{entry::ArchiveEntry, OutputFile};
+
+    #[test]
+    fn check_extracted() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-extracted-{}", std::process::id()));
+        let output = dir.join("delivery");
+        fs::create_dir_all(output.join("docs")).unwrap();
+        fs::write(output.join("a.txt"), "hello").unwrap();
+        fs::write(output.join("docs/b.txt"), "world").unwrap();
+
+        let mut archive = ArchiveContents::new(dir.join("delivery.7z"));
+        for (path, size, crc) in [
+            ("a.txt", 5, "3610A686"),
+            ("docs/b.txt", 5, "00000000"),
+            ("docs/c.txt", 1, "00000000"),
+        ] {
+            archive.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir: false,
+                modified: None,
+                entry: ArchiveEntry {
+                    size: Some(size),
+                    crc: Some(crc.to_string()),
+                    ..Default::default()
+                },
+            });
+        }
+        archive.set_has_root_dir();
+
+        let report = archive.check_extracted(&dir, CompareBy::Size);
+        assert_eq!(report.output, output);
+        assert_eq!(
+            (report.identical, report.different, report.missing),
+            (2, 0, 1)
+        );
+        let report = archive.check_extracted(&dir, CompareBy::Hash);
+        assert_eq!(
+            (report.identical, report.different, report.missing),
+            (1, 1, 1)
+        );
+        assert_eq!(
+            report.entries[1],
+            ExtractedEntry {
+                path: "docs/b.txt".to_string(),
+                status: ExtractedStatus::Different
+            }
+        );
+        assert!(!report.is_extracted());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
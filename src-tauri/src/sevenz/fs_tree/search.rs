@@ -0,0 +1,304 @@
+use ego_tree::iter::Edge;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use time::OffsetDateTime;
+
+use super::{ArchiveContents, FsNode, RawPath};
+use crate::sevenz::error::SevenzError;
+
+/// What the search text is.
+#[derive(Debug, Clone, Deserialize, Type)]
+pub enum SearchPattern {
+    /// Plain text contained in the name.
+    Substring(String),
+    /// `*` and `?` don't cross `/`, `**` does.
+    Glob(String),
+    Regex(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Type)]
+pub enum SearchKind {
+    File,
+    Dir,
+}
+
+/// A search in the listed contents of an archive.
+///
+/// The filters are optional, only the nodes matching all of them are returned.
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    pub pattern: SearchPattern,
+    pub case_sensitive: bool,
+    /// Matches the full path in the archive instead of the name.
+    pub match_path: bool,
+    /// Size in bytes, the total size of the files for folders.
+    #[specta(type = Option<f64>)]
+    pub min_size: Option<u64>,
+    #[specta(type = Option<f64>)]
+    pub max_size: Option<u64>,
+    pub modified_after: Option<OffsetDateTime>,
+    pub modified_before: Option<OffsetDateTime>,
+    pub kind: Option<SearchKind>,
+    /// Stops after this many matches.
+    pub limit: Option<u32>,
+}
+
+/// A node matching a [`SearchQuery`].
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    /// The full path in the archive, separated by `/`.
+    pub path: String,
+    /// The ID of the node, see [`super::Fs`].
+    pub id: String,
+    /// The paths of the folders containing the node, outermost first, for expanding them.
+    pub ancestors: Vec<String>,
+    pub is_dir: bool,
+    /// See [`super::Fs`], only sent when it differs from the UTF-8 of `path`.
+    // default 让 specta 把它导出为可选字段.
+    #[serde(skip_serializing_if = "RawPath::is_utf8_path", default)]
+    #[specta(type = Vec<u8>, optional)]
+    pub raw_path: RawPath,
+}
+
+impl ArchiveContents {
+    /// Returns the nodes matching `query`, in tree order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::InvalidQuery)` if the regex or glob is invalid.
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchMatch>, SevenzError> {
+        let matcher = Matcher::new(query)?;
+        let limit = query.limit.map_or(usize::MAX, |limit| limit as usize);
+        let mut matches = Vec::new();
+        // 当前节点的路径.
+        let mut ancestors: Vec<String> = Vec::new();
+
+        for edge in self.contents.root().traverse() {
+            match edge {
+                Edge::Open(node) => {
+                    let (FsNode::Dir(fs) | FsNode::File(fs)) = node.value() else {
+                        continue;
+                    };
+                    let is_dir = node.value().is_dir();
+                    let size = if is_dir {
+                        fs.stats.size
+                    } else {
+                        fs.entry.size.unwrap_or_default()
+                    };
+                    let text = if query.match_path { &fs.path } else { &fs.name };
+                    if query
+                        .kind
+                        .is_none_or(|kind| (kind == SearchKind::Dir) == is_dir)
+                        && query.min_size.is_none_or(|min| size >= min)
+                        && query.max_size.is_none_or(|max| size <= max)
+                        && modified_in(fs.modified, query)
+                        && matcher.is_match(text)
+                    {
+                        matches.push(SearchMatch {
+                            path: fs.path.clone(),
+                            id: fs.id.clone(),
+                            ancestors: ancestors.clone(),
+                            is_dir,
+                            raw_path: fs.raw_path.clone(),
+                        });
+                        if matches.len() >= limit {
+                            break;
+                        }
+                    }
+                    ancestors.push(fs.path.clone());
+                }
+                Edge::Close(node) => {
+                    if !node.value().is_none() {
+                        ancestors.pop();
+                    }
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+fn modified_in(modified: Option<OffsetDateTime>, query: &SearchQuery) -> bool {
+    if query.modified_after.is_none() && query.modified_before.is_none() {
+        return true;
+    }
+    // 没有修改时间的节点不匹配日期条件.
+    modified.is_some_and(|modified| {
+        query.modified_after.is_none_or(|after| modified >= after)
+            && query
+                .modified_before
+                .is_none_or(|before| modified <= before)
+    })
+}
+
+enum Matcher {
+    /// Lowercase if not case sensitive.
+    Substring(String, bool),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn new(query: &SearchQuery) -> Result<Self, SevenzError> {
+        let regex = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(!query.case_sensitive)
+                .build()
+                .map_err(|err| SevenzError::InvalidQuery(err.to_string()))
+        };
+        Ok(match &query.pattern {
+            SearchPattern::Substring(text) if query.case_sensitive => {
+                Self::Substring(text.clone(), true)
+            }
+            SearchPattern::Substring(text) => Self::Substring(text.to_lowercase(), false),
+            SearchPattern::Glob(glob) => Self::Regex(regex(&glob_to_regex(glob))?),
+            SearchPattern::Regex(pattern) => Self::Regex(regex(pattern)?),
+        })
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Substring(substring, true) => text.contains(substring.as_str()),
+            Self::Substring(substring, false) => text.to_lowercase().contains(substring.as_str()),
+            Self::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Converts a glob to an anchored regex, `[...]` classes are kept as they are.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            _ if in_class => {
+                in_class = c != ']';
+                regex.push(c);
+            }
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                in_class = true;
+                regex.push(c);
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod test_search {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::sevenz::{entry::ArchiveEntry, OutputFile};
+
+    fn archive() -> ArchiveContents {
+        let mut archive = ArchiveContents::new(PathBuf::from("test.7z"));
+        for (path, is_dir, size) in [
+            ("src", true, 0),
+            ("src/main.rs", false, 100),
+            ("src/assets/Logo.PNG", false, 5_000),
+            ("docs/readme.md", false, 10),
+            ("docs/assets", true, 0),
+        ] {
+            archive.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir,
+                modified: None,
+                entry: ArchiveEntry {
+                    size: Some(size),
+                    ..Default::default()
+                },
+            });
+        }
+        archive.sort(&Default::default());
+        archive
+    }
+
+    fn query(pattern: SearchPattern) -> SearchQuery {
+        SearchQuery {
+            pattern,
+            case_sensitive: false,
+            match_path: false,
+            min_size: None,
+            max_size: None,
+            modified_after: None,
+            modified_before: None,
+            kind: None,
+            limit: None,
+        }
+    }
+
+    fn paths(archive: &ArchiveContents, query: &SearchQuery) -> Vec<String> {
+        let matches = archive.search(query).unwrap();
+        matches.into_iter().map(|m| m.path).collect()
+    }
+
+    #[test]
+    fn search() {
+        let archive = archive();
+
+        let found = archive
+            .search(&query(SearchPattern::Substring("logo".into())))
+            .unwrap();
+        assert_eq!(
+            found,
+            [SearchMatch {
+                path: "src/assets/Logo.PNG".into(),
+                id: super::super::path_id("src/assets/Logo.PNG"),
+                ancestors: vec!["src".into(), "src/assets".into()],
+                is_dir: false,
+                raw_path: RawPath::new(b"src/assets/Logo.PNG".to_vec(), "src/assets/Logo.PNG"),
+            }]
+        );
+
+        let mut q = query(SearchPattern::Glob("*.png".into()));
+        q.case_sensitive = true;
+        assert!(paths(&archive, &q).is_empty());
+        q.case_sensitive = false;
+        assert_eq!(paths(&archive, &q), ["src/assets/Logo.PNG"]);
+
+        let mut q = query(SearchPattern::Glob("src/*".into()));
+        q.match_path = true;
+        assert_eq!(paths(&archive, &q), ["src/assets", "src/main.rs"]);
+        q.pattern = SearchPattern::Glob("src/**".into());
+        assert_eq!(
+            paths(&archive, &q),
+            ["src/assets", "src/assets/Logo.PNG", "src/main.rs"]
+        );
+
+        let mut q = query(SearchPattern::Regex(r"^(assets|docs)$".into()));
+        q.kind = Some(SearchKind::Dir);
+        assert_eq!(paths(&archive, &q), ["docs", "docs/assets", "src/assets"]);
+        // folders are as big as their files.
+        q.min_size = Some(1_000);
+        assert_eq!(paths(&archive, &q), ["src/assets"]);
+
+        let mut q = query(SearchPattern::Substring("".into()));
+        q.kind = Some(SearchKind::File);
+        q.limit = Some(2);
+        assert_eq!(
+            paths(&archive, &q),
+            ["docs/readme.md", "src/assets/Logo.PNG"]
+        );
+        q.modified_after = Some(OffsetDateTime::UNIX_EPOCH);
+        assert!(paths(&archive, &q).is_empty());
+
+        let q = query(SearchPattern::Regex("(".into()));
+        assert!(matches!(
+            archive.search(&q),
+            Err(SevenzError::InvalidQuery(_))
+        ));
+    }
+}
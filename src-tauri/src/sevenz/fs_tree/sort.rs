@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+
+use pinyin::ToPinyin;
+use serde::Deserialize;
+use specta::Type;
+use time::OffsetDateTime;
+
+use super::{FsNode, FsTree};
+
+/// How the siblings of the tree are ordered.
+///
+/// The default sorts by name in code point order, like before the options existed.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SortOptions {
+    pub sort_by: SortBy,
+    pub folders_first: bool,
+    /// Compares the digits in names as numbers, `file2` before `file10`.
+    pub natural: bool,
+    pub collation: Collation,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Type)]
+pub enum SortBy {
+    #[default]
+    Name,
+    /// The total size of the files for folders.
+    Size,
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Type)]
+pub enum Collation {
+    #[default]
+    CodePoint,
+    /// Chinese characters by their pinyin among the latin letters, ignoring case.
+    Pinyin,
+}
+
+impl FsTree {
+    /// Sorts the children of every node.
+    pub(super) fn sort(&mut self, options: &SortOptions) {
+        let parents = self
+            .nodes()
+            .filter(|node| node.has_children())
+            .map(|node| node.id())
+            .collect::<Vec<_>>();
+        for parent in parents {
+            let mut children = self
+                .get(parent)
+                .unwrap()
+                .children()
+                .map(|child| (NodeKey::new(child.value(), options), child.id()))
+                .collect::<Vec<_>>();
+            children.sort_by(|(a, _), (b, _)| a.cmp(b, options));
+            let mut parent = self.get_mut(parent).unwrap();
+            for (_, child) in children {
+                parent.append_id(child);
+            }
+        }
+    }
+}
+
+/// What a node is compared by, computed once per node.
+struct NodeKey {
+    is_dir: bool,
+    size: u64,
+    modified: Option<OffsetDateTime>,
+    name: Vec<Segment>,
+    raw_name: String,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+    /// The digits without leading zeros, and their count.
+    Number(usize, String),
+    Text(String),
+}
+
+impl NodeKey {
+    fn new(node: &FsNode, options: &SortOptions) -> Self {
+        let (size, modified) = match node {
+            FsNode::Dir(fs) => (fs.stats.size, fs.modified),
+            FsNode::File(fs) => (fs.entry.size.unwrap_or_default(), fs.modified),
+            FsNode::None => (0, None),
+        };
+        let raw_name = node.name();
+        Self {
+            is_dir: node.is_dir(),
+            size,
+            modified,
+            name: name_key(&raw_name, options),
+            raw_name,
+        }
+    }
+
+    fn cmp(&self, other: &Self, options: &SortOptions) -> Ordering {
+        let folders = if options.folders_first {
+            other.is_dir.cmp(&self.is_dir)
+        } else {
+            Ordering::Equal
+        };
+        let order = match options.sort_by {
+            SortBy::Name => Ordering::Equal,
+            SortBy::Size => self.size.cmp(&other.size),
+            SortBy::Modified => self.modified.cmp(&other.modified),
+        }
+        .then_with(|| self.name.cmp(&other.name))
+        .then_with(|| self.raw_name.cmp(&other.raw_name));
+        folders.then(if options.descending {
+            order.reverse()
+        } else {
+            order
+        })
+    }
+}
+
+fn name_key(name: &str, options: &SortOptions) -> Vec<Segment> {
+    let name = match options.collation {
+        Collation::CodePoint => name.to_string(),
+        Collation::Pinyin => name
+            .chars()
+            .flat_map(|c| match c.to_pinyin() {
+                Some(pinyin) => pinyin.plain().chars().collect::<Vec<_>>(),
+                None => c.to_lowercase().collect(),
+            })
+            .collect(),
+    };
+    if !options.natural {
+        return vec![Segment::Text(name)];
+    }
+
+    let mut segments = Vec::new();
+    let mut rest = name.as_str();
+    while let Some(c) = rest.chars().next() {
+        let is_digit = c.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (part, tail) = rest.split_at(end);
+        segments.push(if is_digit {
+            let digits = part.trim_start_matches('0');
+            Segment::Number(digits.len(), digits.to_string())
+        } else {
+            Segment::Text(part.to_string())
+        });
+        rest = tail;
+    }
+    segments
+}
+
+#[cfg(test)]
+mod test_sort {
+    use super::*;
+    use crate::sevenz::{entry::ArchiveEntry, OutputFile};
+
+    fn tree() -> FsTree {
+        let mut tree = FsTree::new();
+        for (path, is_dir, size) in [
+            ("file10.txt", false, 1),
+            ("file2.txt", false, 30),
+            ("中文", true, 0),
+            ("中文/b", false, 5),
+            ("Apple", false, 20),
+            ("dir", true, 0),
+            ("dir/big", false, 100),
+        ] {
+            tree.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir,
+                modified: None,
+                entry: ArchiveEntry {
+                    size: Some(size),
+                    ..Default::default()
+                },
+            });
+        }
+        tree
+    }
+
+    fn names(tree: &FsTree) -> Vec<String> {
+        tree.root().children().map(|n| n.value().name()).collect()
+    }
+
+    #[test]
+    fn sort() {
+        let mut tree = tree();
+        tree.sort(&SortOptions::default());
+        assert_eq!(
+            names(&tree),
+            ["Apple", "dir", "file10.txt", "file2.txt", "中文"]
+        );
+
+        let mut options = SortOptions {
+            natural: true,
+            folders_first: true,
+            ..Default::default()
+        };
+        tree.sort(&options);
+        assert_eq!(
+            names(&tree),
+            ["dir", "中文", "Apple", "file2.txt", "file10.txt"]
+        );
+
+        options.collation = Collation::Pinyin;
+        tree.sort(&options);
+        assert_eq!(
+            names(&tree),
+            ["dir", "中文", "Apple", "file2.txt", "file10.txt"]
+        );
+        options.folders_first = false;
+        tree.sort(&options);
+        assert_eq!(
+            names(&tree),
+            ["Apple", "dir", "file2.txt", "file10.txt", "中文"]
+        );
+
+        // folders by the size of their files.
+        options.sort_by = SortBy::Size;
+        options.descending = true;
+        tree.sort(&options);
+        assert_eq!(
+            names(&tree),
+            ["dir", "file2.txt", "Apple", "中文", "file10.txt"]
+        );
+    }
+
+    #[test]
+    fn pinyin_among_latin() {
+        let mut tree = FsTree::new();
+        for path in ["上海", "Banana", "阿", "北京", "Apple"] {
+            tree.append_file(OutputFile {
+                path: path.to_string(),
+                raw_path: None,
+                is_dir: false,
+                modified: None,
+                entry: ArchiveEntry::default(),
+            });
+        }
+        let mut options = SortOptions::default();
+        tree.sort(&options);
+        let code_point = names(&tree);
+        assert_eq!(code_point, ["Apple", "Banana", "上海", "北京", "阿"]);
+
+        // 阿 (a) 在 Apple 之前, 北京 (beijing) 在 上海 (shanghai) 之前.
+        options.collation = Collation::Pinyin;
+        tree.sort(&options);
+        assert_eq!(names(&tree), ["阿", "Apple", "Banana", "北京", "上海"]);
+        assert_ne!(names(&tree), code_point);
+    }
+}
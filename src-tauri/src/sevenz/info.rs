@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+
+use regex::Regex;
+use serde::Serialize;
+use specta::Type;
+
+use super::detect::{parse_banner, SevenzFlavor};
+
+/// What a 7z executable is and what it can open, parsed from `7z i`.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SevenZipInfo {
+    pub version: String,
+    /// `x64`, `arm64`..., or `64-bit` for p7zip.
+    pub architecture: Option<String>,
+    /// `YYYY-MM-DD`.
+    pub build_date: Option<String>,
+    pub flavor: SevenzFlavor,
+    /// Archive format names, like `7z`, `zip` or `Rar5`.
+    pub formats: Vec<String>,
+    /// Codec names, like `LZMA2`, `Deflate` or `7zAES`.
+    pub codecs: Vec<String>,
+}
+
+/// Parsed `7z i` output by 7z command, `7z i` is only run once per command.
+static INFO_CACHE: LazyLock<Mutex<HashMap<String, SevenZipInfo>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Signatures of the formats checked before listing, named like in `7z i`.
+///
+/// RAR5 comes before RAR4, its signature is longer.
+const SIGNATURES: [(&str, &[u8]); 9] = [
+    ("7z", b"7z\xBC\xAF\x27\x1C"),
+    ("Rar5", b"Rar!\x1A\x07\x01\x00"),
+    ("Rar", b"Rar!\x1A\x07\x00"),
+    ("zip", b"PK\x03\x04"),
+    ("gzip", b"\x1F\x8B"),
+    ("bzip2", b"BZh"),
+    ("xz", b"\xFD7zXZ\x00"),
+    ("zstd", b"\x28\xB5\x2F\xFD"),
+    ("Cab", b"MSCF"),
+];
+
+#[derive(PartialEq)]
+enum Section {
+    Header,
+    Formats,
+    Codecs,
+    Other,
+}
+
+impl SevenZipInfo {
+    /// Returns the cached info of `command`, or runs `load` to get it.
+    pub fn cached<F>(command: &str, load: F) -> Option<Self>
+    where
+        F: FnOnce() -> Option<Self>,
+    {
+        if let Some(info) = INFO_CACHE.lock().unwrap().get(command) {
+            return Some(info.clone());
+        }
+        let info = load()?;
+        INFO_CACHE
+            .lock()
+            .unwrap()
+            .insert(command.to_string(), info.clone());
+        Some(info)
+    }
+
+    /// Drops the cached info of `command`, after it was replaced by another version.
+    pub fn forget(command: &str) {
+        INFO_CACHE.lock().unwrap().remove(command);
+    }
+
+    /// Parses the output of `7z i`.
+    pub fn parse(output: &str) -> Option<Self> {
+        let (version, flavor) = parse_banner(output)?;
+        let banner = output.lines().find(|line| line.starts_with("7-Zip"))?;
+        let architecture = Regex::new(r"\d+\.\d+(?: \w+)? \(([^)]+)\)")
+            .unwrap()
+            .captures(banner)
+            .map(|caps| caps[1].to_string())
+            .or_else(|| {
+                let re = Regex::new(r"^7-Zip \[(\d+)\]").unwrap();
+                re.captures(banner).map(|caps| format!("{}-bit", &caps[1]))
+            });
+        let build_date = Regex::new(r"\d{4}-\d{2}-\d{2}")
+            .unwrap()
+            .find_iter(banner)
+            .last()
+            .map(|date| date.as_str().to_string());
+
+        // The format lines start with a fixed width flags column, its width is known from the
+        // `7z` format that every build has.
+        let name_column = Regex::new(r"(?m)^(.*\s)7z\s+7z\s")
+            .unwrap()
+            .captures(output)
+            .map(|caps| caps[1].len());
+
+        let mut formats = Vec::new();
+        let mut codecs = Vec::new();
+        let mut section = Section::Header;
+        for line in output.lines() {
+            match line.trim_end() {
+                "" => continue,
+                "Formats:" => section = Section::Formats,
+                "Codecs:" => section = Section::Codecs,
+                header if header.ends_with(':') && !header.starts_with(' ') => {
+                    section = Section::Other
+                }
+                line if section == Section::Formats => {
+                    let name = name_column
+                        .and_then(|column| line.get(column..))
+                        .and_then(|rest| rest.split_whitespace().next());
+                    if let Some(name) = name {
+                        formats.push(name.to_string());
+                    }
+                }
+                line if section == Section::Codecs => {
+                    if let Some(name) = line.split_whitespace().last() {
+                        codecs.push(name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            version,
+            architecture,
+            build_date,
+            flavor,
+            formats,
+            codecs,
+        })
+    }
+
+    /// Whether the format named like in `7z i` is supported.
+    pub fn supports(&self, format: &str) -> bool {
+        self.formats.iter().any(|f| f.eq_ignore_ascii_case(format))
+    }
+
+    /// Returns the format of the archive if it is known and this 7z can't open it.
+    ///
+    /// For example RAR5 archives with a p7zip build without the RAR plugin.
+    pub fn unsupported_format(&self, archive_path: &Path) -> Option<&'static str> {
+        // 没有解析出格式时当作未知, 不能拒绝所有压缩文件.
+        if self.formats.is_empty() {
+            return None;
+        }
+        let format = archive_format(archive_path)?;
+        (!self.supports(format)).then_some(format)
+    }
+}
+
+/// Guesses the archive format from the first bytes of the file.
+pub fn archive_format(path: &Path) -> Option<&'static str> {
+    let mut magic = Vec::with_capacity(8);
+    File::open(path)
+        .ok()?
+        .take(8)
+        .read_to_end(&mut magic)
+        .ok()?;
+    SIGNATURES
+        .iter()
+        .find(|(_, signature)| magic.starts_with(signature))
+        .map(|(format, _)| *format)
+}
+
+#[cfg(test)]
+mod test_info {
+    use super::*;
+
+    const SEVENZZ: &str = "
+7-Zip (z) 24.08 (x64) : Copyright (c) 1999-2024 Igor Pavlov : 2024-08-11
+ 64-bit locale=C.UTF-8 Threads:8 OPEN_MAX:1024, ASM
+
+Formats:
+ ...  F                  APM      apm          E R
+ C...F..........c.a.m+.. 7z       7z           7 z BC AF ' 1C
+ ......................  Rar      rar r00      R a r ! 1A 07 00
+ ......................  Rar5     rar          R a r ! 1A 07 01 00
+ C...F.................  zip      zip z01 zipx jar xpi odt ods docx xlsx epub ipa apk appx  P K 03 04
+
+Codecs:
+ 4ED   303011B BCJ2
+  ED   3030103 BCJ
+  ED        21 LZMA2
+  ED   6F10701 7zAES
+
+Hashers:
+   4        1 CRC32
+";
+
+    const P7ZIP: &str = "
+7-Zip [64] 16.02 : Copyright (c) 1999-2016 Igor Pavlov : 2016-05-21
+p7zip Version 16.02 (locale=en_US.UTF-8,Utf16=on,HugeFiles=on,64 bits,8 CPUs x64)
+
+Libs:
+ 0  /usr/lib/p7zip/7z.so
+
+Formats:
+ 0 C.SN.TF    7z       7z            7 z BC AF ' 1C
+ 0  ...  F    APM      apm           E R
+ 0 C...  F    zip      zip z01 zipx jar xpi odt ods docx xlsx epub  P K 03 04
+
+Codecs:
+ 0 4ED   303011B BCJ2
+ 0  ED        21 LZMA2
+";
+
+    #[test]
+    fn parse() {
+        let info = SevenZipInfo::parse(SEVENZZ).unwrap();
+        assert_eq!(info.version, "24.08");
+        assert_eq!(info.architecture.as_deref(), Some("x64"));
+        assert_eq!(info.build_date.as_deref(), Some("2024-08-11"));
+        assert_eq!(info.flavor, SevenzFlavor::SevenZip);
+        assert_eq!(info.formats, ["APM", "7z", "Rar", "Rar5", "zip"]);
+        assert_eq!(info.codecs, ["BCJ2", "BCJ", "LZMA2", "7zAES"]);
+        assert!(info.supports("rar5"));
+
+        let info = SevenZipInfo::parse(P7ZIP).unwrap();
+        assert_eq!(info.version, "16.02");
+        assert_eq!(info.architecture.as_deref(), Some("64-bit"));
+        assert_eq!(info.build_date.as_deref(), Some("2016-05-21"));
+        assert_eq!(info.flavor, SevenzFlavor::P7zip);
+        assert_eq!(info.formats, ["7z", "APM", "zip"]);
+        assert_eq!(info.codecs, ["BCJ2", "LZMA2"]);
+
+        let path = std::env::temp_dir().join(format!("e-zip-test-info-{}", std::process::id()));
+        std::fs::write(&path, b"Rar!\x1A\x07\x01\x00rest").unwrap();
+        assert_eq!(info.unsupported_format(&path), Some("Rar5"));
+        let unknown = SevenZipInfo {
+            formats: Vec::new(),
+            ..info
+        };
+        assert_eq!(unknown.unsupported_format(&path), None);
+        std::fs::remove_file(path).unwrap();
+    }
+}
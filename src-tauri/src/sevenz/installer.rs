@@ -0,0 +1,567 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use tauri_plugin_http::reqwest;
+
+use super::{detect::SevenzCandidate, error::SevenzError};
+
+/// The official download directory, used when no mirror is configured.
+pub const DEFAULT_MIRROR: &str = "https://7-zip.org/a";
+
+/// The releases the installer accepts, see `7z-manifest.toml`.
+static MANIFEST: LazyLock<Manifest> = LazyLock::new(|| {
+    toml::from_str(include_str!("../../7z-manifest.toml")).expect("invalid 7z-manifest.toml")
+});
+
+/// Where the 7-Zip release archive comes from.
+#[derive(Debug, Clone)]
+pub enum InstallSource {
+    /// Downloads the pinned release from the base URL, `https://7-zip.org/a` if `None`.
+    Mirror(Option<String>),
+    /// Installs from a release archive already on disk, for machines without internet.
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    release: Vec<Release>,
+}
+
+/// A release archive and its SHA-256 checksum.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    file: String,
+    sha256: String,
+}
+
+impl Release {
+    /// The version from the file name, `7z2409-linux-x64.tar.xz` is `24.09`.
+    pub fn version(&self) -> Option<String> {
+        release_version(&self.file)
+    }
+}
+
+/// The version of a release file name like `7z2409-linux-x64.tar.xz`.
+pub fn release_version(file_name: &str) -> Option<String> {
+    let digits = file_name.strip_prefix("7z")?.split('-').next()?;
+    if digits.len() < 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}.{}", &digits[..2], &digits[2..]))
+}
+
+/// The pinned manifest of the installer.
+pub fn manifest() -> &'static Manifest {
+    &MANIFEST
+}
+
+impl Manifest {
+    /// The newest release for this platform.
+    pub fn newest_release(&self) -> Option<&Release> {
+        self.platform_releases().next()
+    }
+
+    /// The releases for this platform, newest first.
+    fn platform_releases(&self) -> impl Iterator<Item = &Release> {
+        let suffix = platform_suffix();
+        self.release
+            .iter()
+            .filter(move |release| release.file.ends_with(suffix))
+    }
+}
+
+/// The end of the release file name for this platform.
+pub fn platform_suffix() -> &'static str {
+    if cfg!(windows) {
+        "extra.7z"
+    } else if cfg!(target_os = "macos") {
+        "mac.tar.xz"
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        "linux-arm64.tar.xz"
+    } else if cfg!(target_os = "linux") {
+        "linux-x64.tar.xz"
+    } else {
+        panic!("Unsupported operating system");
+    }
+}
+
+/// The directory of the private 7z command in the config directory.
+pub fn install_dir_name() -> &'static str {
+    if cfg!(windows) {
+        "7z-extra"
+    } else if cfg!(target_os = "macos") {
+        "7z-macos"
+    } else if cfg!(target_os = "linux") {
+        "7z-linux"
+    } else {
+        "7z-unknown"
+    }
+}
+
+/// A step of the installation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+pub enum InstallPhase {
+    /// Choosing the release in the manifest.
+    Resolve,
+    Download,
+    /// Checking the SHA-256 checksum.
+    Verify,
+    Unpack,
+}
+
+/// Progress of the installation, `bytes` of `total` in the current phase.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallProgress {
+    pub phase: InstallPhase,
+    #[specta(type = f64)]
+    pub bytes: u64,
+    #[specta(type = Option<f64>)]
+    pub total: Option<u64>,
+}
+
+impl InstallProgress {
+    fn new(phase: InstallPhase, bytes: u64, total: Option<u64>) -> Self {
+        Self {
+            phase,
+            bytes,
+            total,
+        }
+    }
+}
+
+/// Set by [`cancel_install`], checked between chunks and entries.
+static CANCEL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Cancels the running installation.
+///
+/// The installation stops with `Err(SevenzError::Cancelled)` and removes the partial download.
+pub fn cancel_install() {
+    CANCEL_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+fn check_cancelled() -> Result<(), SevenzError> {
+    if CANCEL_REQUESTED.load(Ordering::SeqCst) {
+        return Err(SevenzError::Cancelled);
+    }
+    Ok(())
+}
+
+/// Installs the private 7z command into the config directory.
+///
+/// The release archive is verified against the pinned manifest before it is unpacked, and the
+/// previous installation is only replaced once the new command runs.
+pub async fn install_7z<F>(
+    config_dir: &Path,
+    source: InstallSource,
+    on_progress: F,
+) -> Result<SevenzCandidate, SevenzError>
+where
+    F: Fn(InstallProgress) + Send + Sync,
+{
+    install_7z_with(&MANIFEST, config_dir, source, on_progress).await
+}
+
+/// Installs with the given manifest instead of the pinned one.
+pub async fn install_7z_with<F>(
+    manifest: &Manifest,
+    config_dir: &Path,
+    source: InstallSource,
+    on_progress: F,
+) -> Result<SevenzCandidate, SevenzError>
+where
+    F: Fn(InstallProgress) + Send + Sync,
+{
+    CANCEL_REQUESTED.store(false, Ordering::SeqCst);
+    fs::create_dir_all(config_dir)?;
+    on_progress(InstallProgress::new(InstallPhase::Resolve, 0, None));
+    match source {
+        InstallSource::Mirror(mirror) => {
+            let release = manifest.newest_release().ok_or_else(|| {
+                SevenzError::InstallError(format!("没有已校验的 7-Zip 版本: {}", platform_suffix()))
+            })?;
+            let mirror = mirror.as_deref().unwrap_or(DEFAULT_MIRROR);
+            let url = format!("{}/{}", mirror.trim_end_matches('/'), release.file);
+            let part_path = config_dir.join(format!("{}.part", release.file));
+            let result = download(&url, &part_path, release, &on_progress).await;
+            let result = match result {
+                Ok(()) => install_release(config_dir, &part_path, &release.file, &on_progress),
+                Err(err) => Err(err),
+            };
+            let _ = fs::remove_file(&part_path);
+            result
+        }
+        InstallSource::File(path) => {
+            let sha256 = file_sha256(&path, &on_progress)?;
+            let release = manifest
+                .platform_releases()
+                .find(|release| release.sha256.eq_ignore_ascii_case(&sha256))
+                .ok_or(SevenzError::ChecksumMismatch(path.clone()))?;
+            install_release(config_dir, &path, &release.file, &on_progress)
+        }
+    }
+}
+
+/// Downloads `url` to `path`, and checks the checksum of `release`.
+async fn download<F>(
+    url: &str,
+    path: &Path,
+    release: &Release,
+    on_progress: &F,
+) -> Result<(), SevenzError>
+where
+    F: Fn(InstallProgress) + Send + Sync,
+{
+    let mut res = reqwest::get(url).await?.error_for_status()?;
+    let total = res.content_length();
+    let mut bytes = 0;
+    let mut hasher = Sha256::new();
+    let mut dest = File::create(path)?;
+    on_progress(InstallProgress::new(InstallPhase::Download, bytes, total));
+    while let Some(chunk) = res.chunk().await? {
+        check_cancelled()?;
+        hasher.update(&chunk);
+        dest.write_all(&chunk)?;
+        bytes += chunk.len() as u64;
+        on_progress(InstallProgress::new(InstallPhase::Download, bytes, total));
+    }
+    dest.sync_all()?;
+    // The checksum is computed while downloading.
+    on_progress(InstallProgress::new(
+        InstallPhase::Verify,
+        bytes,
+        Some(bytes),
+    ));
+    if !hex(&hasher.finalize()).eq_ignore_ascii_case(&release.sha256) {
+        return Err(SevenzError::ChecksumMismatch(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+fn file_sha256<F>(path: &Path, on_progress: &F) -> Result<String, SevenzError>
+where
+    F: Fn(InstallProgress),
+{
+    let mut file = BufReader::new(File::open(path)?);
+    let total = fs::metadata(path)?.len();
+    let mut bytes = 0;
+    let mut hasher = Sha256::new();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        check_cancelled()?;
+        on_progress(InstallProgress::new(
+            InstallPhase::Verify,
+            bytes,
+            Some(total),
+        ));
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        bytes += n as u64;
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Unpacks the verified release next to the install directory, then swaps it in.
+///
+/// The replaced installation is kept in [`previous_dir_name`] as a fallback. On failure it is
+/// restored and nothing is left behind.
+fn install_release<F>(
+    config_dir: &Path,
+    archive_path: &Path,
+    file_name: &str,
+    on_progress: &F,
+) -> Result<SevenzCandidate, SevenzError>
+where
+    F: Fn(InstallProgress),
+{
+    let target_dir = config_dir.join(install_dir_name());
+    let staging_dir = config_dir.join(format!(".{}.new", install_dir_name()));
+    let previous_dir = config_dir.join(previous_dir_name());
+    let _ = fs::remove_dir_all(&staging_dir);
+
+    let staged =
+        unpack_release(archive_path, file_name, &staging_dir, on_progress).and_then(|()| {
+            let command = staging_dir.join(command_file_name());
+            SevenzCandidate::probe(&command).ok_or(SevenzError::Invalid7z(command))
+        });
+    if let Err(err) = staged {
+        let _ = fs::remove_dir_all(&staging_dir);
+        return Err(err);
+    }
+
+    let had_previous = target_dir.exists();
+    if had_previous {
+        let _ = fs::remove_dir_all(&previous_dir);
+        fs::rename(&target_dir, &previous_dir)?;
+    }
+    let installed = fs::rename(&staging_dir, &target_dir)
+        .map_err(SevenzError::from)
+        .and_then(|()| {
+            let command = target_dir.join(command_file_name());
+            SevenzCandidate::probe(&command).ok_or(SevenzError::Invalid7z(command))
+        });
+    if installed.is_err() {
+        // Rollback.
+        let _ = fs::remove_dir_all(&staging_dir);
+        if had_previous {
+            let _ = fs::remove_dir_all(&target_dir);
+            fs::rename(&previous_dir, &target_dir)?;
+        }
+    }
+    installed
+}
+
+/// The directory of the private 7z command replaced by the last installation.
+pub fn previous_dir_name() -> String {
+    format!("{}-previous", install_dir_name())
+}
+
+/// The file name of the private 7z command.
+pub fn command_file_name() -> &'static str {
+    if cfg!(windows) {
+        "7za.exe"
+    } else {
+        "7zz"
+    }
+}
+
+fn unpack_release<F>(
+    archive_path: &Path,
+    file_name: &str,
+    dir: &Path,
+    on_progress: &F,
+) -> Result<(), SevenzError>
+where
+    F: Fn(InstallProgress),
+{
+    fs::create_dir_all(dir)?;
+    on_progress(InstallProgress::new(InstallPhase::Unpack, 0, None));
+    if file_name.ends_with(".tar.xz") {
+        let decoder =
+            xz2::read::XzDecoder::new_multi_decoder(BufReader::new(File::open(archive_path)?));
+        let mut tar = tar::Archive::new(decoder);
+        tar.set_preserve_permissions(true);
+        let mut bytes = 0;
+        for entry in tar.entries()? {
+            check_cancelled()?;
+            let mut entry = entry?;
+            entry.unpack_in(dir)?;
+            bytes += entry.size();
+            on_progress(InstallProgress::new(InstallPhase::Unpack, bytes, None));
+        }
+        Ok(())
+    } else {
+        // The Windows release is a 7z archive, unpacked by the bsdtar shipped with Windows.
+        let output = Command::new("tar")
+            .arg("-xf")
+            .arg(archive_path)
+            .arg("-C")
+            .arg(dir)
+            .output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(SevenzError::InstallError(format!(
+                "解压 7-Zip 失败: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )))
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test_installer {
+    use std::{
+        io::{self, BufRead},
+        net::TcpListener,
+        os::unix::fs::PermissionsExt,
+        sync::Mutex,
+        thread,
+    };
+
+    use xz2::write::XzEncoder;
+
+    use super::*;
+
+    const FAKE_7ZZ: &str = "#!/bin/sh\n\
+        echo '7-Zip (z) 24.09 (x64) : Copyright (c) 1999-2024 Igor Pavlov : 2024-11-29'\n";
+
+    fn release_tar_xz(script: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(XzEncoder::new(Vec::new(), 6));
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o755);
+        header.set_size(script.len() as u64);
+        builder
+            .append_data(&mut header, "7zz", script.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn manifest(file: &str, bytes: &[u8]) -> Manifest {
+        Manifest {
+            release: vec![Release {
+                file: file.to_string(),
+                sha256: hex(&Sha256::digest(bytes)),
+            }],
+        }
+    }
+
+    /// Serves `body` to every request, like a mirror would.
+    fn serve(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(header.as_bytes()).unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        format!("http://{}/mirror/", addr)
+    }
+
+    #[test]
+    #[ignore = "7z-manifest.toml 需要联网固定版本, 见 scripts/pin-7z-release.sh"]
+    fn shipped_manifest() {
+        for suffix in [
+            "extra.7z",
+            "mac.tar.xz",
+            "linux-x64.tar.xz",
+            "linux-arm64.tar.xz",
+        ] {
+            let release = MANIFEST
+                .release
+                .iter()
+                .find(|release| release.file.ends_with(suffix));
+            assert!(
+                release.is_some_and(
+                    |release| release.version().is_some() && release.sha256.len() == 64
+                ),
+                "no release for {suffix}"
+            );
+        }
+    }
+
+    #[test]
+    fn install_from_mirror_and_file() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-installer-{}", std::process::id()));
+        let config_dir = dir.join("config");
+        let file = format!("7z2409-{}", platform_suffix());
+        let release = release_tar_xz(FAKE_7ZZ);
+        let manifest = manifest(&file, &release);
+
+        let mirror = serve(release.clone());
+        let phases = Mutex::new(Vec::new());
+        let candidate = tauri::async_runtime::block_on(install_7z_with(
+            &manifest,
+            &config_dir,
+            InstallSource::Mirror(Some(mirror.clone())),
+            |progress| {
+                let mut phases = phases.lock().unwrap();
+                if phases.last() != Some(&progress.phase) {
+                    phases.push(progress.phase);
+                }
+            },
+        ))
+        .unwrap();
+        assert_eq!(
+            phases.into_inner().unwrap(),
+            [
+                InstallPhase::Resolve,
+                InstallPhase::Download,
+                InstallPhase::Verify,
+                InstallPhase::Unpack
+            ]
+        );
+        assert_eq!(candidate.version, "24.09");
+        let target_dir = config_dir.join(install_dir_name());
+        assert!(target_dir.join("7zz").is_file());
+        assert!(!config_dir.join(format!("{}.part", file)).exists());
+
+        // Cancelling removes the partial download.
+        let result = tauri::async_runtime::block_on(install_7z_with(
+            &manifest,
+            &config_dir,
+            InstallSource::Mirror(Some(mirror)),
+            |progress| {
+                if progress.phase == InstallPhase::Download {
+                    cancel_install();
+                }
+            },
+        ));
+        assert!(matches!(result, Err(SevenzError::Cancelled)));
+        assert!(!config_dir.join(format!("{}.part", file)).exists());
+
+        // A tampered archive is rejected and the installed command is kept.
+        let tampered = dir.join(&file);
+        fs::write(&tampered, release_tar_xz("#!/bin/sh\nexit 1\n")).unwrap();
+        let result = tauri::async_runtime::block_on(install_7z_with(
+            &manifest,
+            &config_dir,
+            InstallSource::File(tampered.clone()),
+            |_| {},
+        ));
+        assert!(matches!(result, Err(SevenzError::ChecksumMismatch(_))));
+        assert_eq!(
+            fs::read_to_string(target_dir.join("7zz")).unwrap(),
+            FAKE_7ZZ
+        );
+
+        // A verified release that doesn't run is not installed.
+        let result = install_release(&config_dir, &tampered, &file, &|_| {});
+        assert!(matches!(result, Err(SevenzError::Invalid7z(_))));
+        assert_eq!(
+            fs::read_to_string(target_dir.join("7zz")).unwrap(),
+            FAKE_7ZZ
+        );
+
+        let local = dir.join("local.tar.xz");
+        fs::write(&local, &release).unwrap();
+        let candidate = tauri::async_runtime::block_on(install_7z_with(
+            &manifest,
+            &config_dir,
+            InstallSource::File(local),
+            |_| {},
+        ))
+        .unwrap();
+        assert_eq!(candidate.path, target_dir.join("7zz"));
+        let mode = fs::metadata(target_dir.join("7zz"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o755, 0o755);
+        assert!(config_dir.join(previous_dir_name()).join("7zz").is_file());
+        assert_eq!(fs::read_dir(&config_dir).unwrap().count(), 2);
+        assert_eq!(release_version(&file).as_deref(), Some("24.09"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    io,
+    ops::Deref,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc, Arc, LazyLock, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{backend::ExtractProcess, error::SevenzError, queue, unzip::UnzipedArchiveStatus};
+
+/// The ID of an extraction, sent with [`UnzipedArchiveStatus::Job`].
+pub type JobId = u32;
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+static JOBS: LazyLock<Mutex<HashMap<JobId, Arc<Job>>>> = LazyLock::new(Default::default);
+/// How often [`Job::wait`] checks whether the process finished.
+const WAIT_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JobState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+/// The extraction of an archive, from the time it is queued.
+///
+/// The process is replaced for each password tried, the state applies to all of them.
+pub struct Job {
+    id: JobId,
+    archive: PathBuf,
+    state: Mutex<JobState>,
+    process: Mutex<Option<Box<dyn ExtractProcess>>>,
+    sender: mpsc::Sender<(PathBuf, UnzipedArchiveStatus)>,
+}
+
+/// Removes the job when the extraction ends.
+pub struct JobGuard(Arc<Job>);
+
+impl Deref for JobGuard {
+    type Target = Job;
+
+    fn deref(&self) -> &Job {
+        &self.0
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        JOBS.lock().unwrap().remove(&self.0.id);
+    }
+}
+
+impl Job {
+    /// Registers the extraction of `archive`, its ID is sent when it is queued.
+    pub fn register(
+        archive: PathBuf,
+        sender: &mpsc::Sender<(PathBuf, UnzipedArchiveStatus)>,
+    ) -> JobGuard {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let job = Arc::new(Job {
+            id,
+            archive,
+            state: Mutex::new(JobState::Running),
+            process: Mutex::new(None),
+            sender: sender.clone(),
+        });
+        JOBS.lock().unwrap().insert(id, job.clone());
+        JobGuard(job)
+    }
+
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Sends [`UnzipedArchiveStatus::Running`] when the extraction leaves the queue, or
+    /// [`UnzipedArchiveStatus::Paused`] if it was paused while queued.
+    pub fn send_started(&self) {
+        let state = self.state.lock().unwrap();
+        match *state {
+            JobState::Paused => self.send(UnzipedArchiveStatus::Paused),
+            _ => self.send(UnzipedArchiveStatus::Running),
+        }
+    }
+
+    /// Runs `process` for the job, paused or killed if the job already is.
+    pub fn attach(&self, mut process: Box<dyn ExtractProcess>) {
+        let state = self.state.lock().unwrap();
+        let result = match *state {
+            JobState::Running => Ok(()),
+            JobState::Paused => process.pause(),
+            JobState::Cancelled => process.kill(),
+        };
+        if let Err(err) = result {
+            log::warn!("job {} {:?} failed: {}", self.id, *state, err);
+        }
+        *self.process.lock().unwrap() = Some(process);
+    }
+
+    /// Removes the finished process.
+    pub fn detach(&self) {
+        self.process.lock().unwrap().take();
+    }
+
+    /// Waits for the process to finish, see [`ExtractProcess::wait`].
+    ///
+    /// The process is only locked to check it, so it can be paused or killed meanwhile.
+    pub fn wait(&self) -> io::Result<bool> {
+        loop {
+            match self.process.lock().unwrap().as_mut() {
+                Some(process) => {
+                    if let Some(success) = process.try_wait()? {
+                        return Ok(success);
+                    }
+                }
+                None => return Ok(false),
+            }
+            thread::sleep(WAIT_INTERVAL);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.state.lock().unwrap() == JobState::Cancelled
+    }
+
+    fn send(&self, status: UnzipedArchiveStatus) {
+        let _ = self.sender.send((self.archive.clone(), status));
+    }
+
+    fn set_state(&self, target: JobState) -> Result<(), SevenzError> {
+        let mut state = self.state.lock().unwrap();
+        // 取消后不能再暂停或继续.
+        if *state == target || *state == JobState::Cancelled {
+            return Ok(());
+        }
+        if let Some(process) = self.process.lock().unwrap().as_mut() {
+            match target {
+                JobState::Running => process.resume()?,
+                JobState::Paused => process.pause()?,
+                JobState::Cancelled => process.kill()?,
+            }
+        }
+        *state = target;
+        match target {
+            JobState::Running => self.send(UnzipedArchiveStatus::Running),
+            JobState::Paused => self.send(UnzipedArchiveStatus::Paused),
+            // 清理临时文件夹后由解压线程发送.
+            JobState::Cancelled => {}
+        }
+        Ok(())
+    }
+}
+
+fn find(id: JobId) -> Result<Arc<Job>, SevenzError> {
+    JOBS.lock()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or(SevenzError::JobNotFound(id))
+}
+
+/// Stops the extraction, the staged files are removed and
+/// [`UnzipedArchiveStatus::Cancelled`] is sent.
+///
+/// An extraction still waiting in the queue is removed from it.
+pub fn cancel(id: JobId) -> Result<(), SevenzError> {
+    let job = find(id)?;
+    job.set_state(JobState::Cancelled)?;
+    if queue::remove(id) {
+        job.send(UnzipedArchiveStatus::Cancelled);
+    }
+    Ok(())
+}
+
+pub fn pause(id: JobId) -> Result<(), SevenzError> {
+    find(id)?.set_state(JobState::Paused)
+}
+
+pub fn resume(id: JobId) -> Result<(), SevenzError> {
+    find(id)?.set_state(JobState::Running)
+}
+
+#[cfg(test)]
+mod test_job {
+    use super::*;
+    use crate::sevenz::backend::ThreadProcess;
+
+    #[test]
+    fn cancel_while_waiting() {
+        let (tx, rx) = mpsc::channel();
+        let job = Job::register("running.zip".into(), &tx);
+        let id = job.id();
+        job.attach(Box::new(ThreadProcess::spawn(|control| loop {
+            control.checkpoint()?;
+            thread::sleep(Duration::from_millis(10));
+        })));
+        thread::scope(|scope| {
+            let waiter = scope.spawn(|| job.wait());
+            thread::sleep(Duration::from_millis(100));
+            // wait 不持有进程的锁, 暂停和取消不会阻塞.
+            pause(id).unwrap();
+            cancel(id).unwrap();
+            assert!(!waiter.join().unwrap().unwrap());
+        });
+        assert!(job.is_cancelled());
+        drop((job, tx));
+        assert!(matches!(
+            rx.into_iter().map(|(_, status)| status).collect::<Vec<_>>()[..],
+            [UnzipedArchiveStatus::Paused]
+        ));
+    }
+}
@@ -0,0 +1,335 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    mem,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, LazyLock, Mutex, MutexGuard},
+    thread,
+};
+
+use super::{error::SevenzError, job::JobId, unzip::UnzipedArchiveStatus};
+use crate::config::DEFAULT_MAX_CONCURRENCY;
+
+static QUEUE: LazyLock<Arc<ExtractQueue>> =
+    LazyLock::new(|| Arc::new(ExtractQueue::new(DEFAULT_MAX_CONCURRENCY)));
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// An extraction waiting for a free slot.
+struct Pending {
+    archive: PathBuf,
+    /// The folder the archive is extracted into, see [`super::unzip::output_dir`].
+    output_dir: PathBuf,
+    job: JobId,
+    priority: i32,
+    /// Keeps the order of the archives with the same priority.
+    seq: u64,
+    task: Task,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    /// The highest priority first, then the first queued.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct QueueState {
+    pending: BinaryHeap<Pending>,
+    /// The archives being extracted and their output folders.
+    ///
+    /// An archive is never extracted twice at once, nor two archives into the same folder.
+    running: HashMap<PathBuf, PathBuf>,
+    max_concurrency: usize,
+    next_seq: u64,
+}
+
+/// Runs the extractions on their own threads, at most `max_concurrency` at once.
+pub struct ExtractQueue(Mutex<QueueState>);
+
+/// Frees the slot of the extraction, even if it panics.
+struct Slot {
+    queue: Arc<ExtractQueue>,
+    archive: PathBuf,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut state = self.queue.0.lock().unwrap();
+        state.running.remove(&self.archive);
+        self.queue.dispatch(state);
+    }
+}
+
+impl ExtractQueue {
+    fn new(max_concurrency: usize) -> Self {
+        ExtractQueue(Mutex::new(QueueState {
+            pending: BinaryHeap::new(),
+            running: HashMap::new(),
+            max_concurrency,
+            next_seq: 0,
+        }))
+    }
+
+    /// Queues `task` for `archive` and sends [`UnzipedArchiveStatus::Queued`], then the ID
+    /// of its `job`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::AlreadyQueued)` if the archive is already queued or extracting.
+    fn push(
+        self: &Arc<Self>,
+        archive: PathBuf,
+        output_dir: PathBuf,
+        job: JobId,
+        priority: i32,
+        sender: &mpsc::Sender<(PathBuf, UnzipedArchiveStatus)>,
+        task: Task,
+    ) -> Result<(), SevenzError> {
+        let mut state = self.0.lock().unwrap();
+        if state.running.contains_key(&archive)
+            || state.pending.iter().any(|p| p.archive == archive)
+        {
+            return Err(SevenzError::AlreadyQueued(archive));
+        }
+        // 在启动前发送, 保证 Queued 在 Running 之前. 接收端关闭时不影响排队.
+        let _ = sender.send((archive.clone(), UnzipedArchiveStatus::Queued));
+        let _ = sender.send((archive.clone(), UnzipedArchiveStatus::Job(job)));
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.pending.push(Pending {
+            archive,
+            output_dir,
+            job,
+            priority,
+            seq,
+            task,
+        });
+        self.dispatch(state);
+        Ok(())
+    }
+
+    /// Starts the pending extractions while there are free slots.
+    ///
+    /// An extraction whose output folder contains or is inside the one of a running extraction
+    /// waits for it, even if it has a higher priority.
+    fn dispatch(self: &Arc<Self>, mut state: MutexGuard<QueueState>) {
+        let mut held = Vec::new();
+        while state.running.len() < state.max_concurrency.max(1) {
+            let Some(pending) = state.pending.pop() else {
+                break;
+            };
+            if state.running.values().any(|output_dir| {
+                output_dir.starts_with(&pending.output_dir)
+                    || pending.output_dir.starts_with(output_dir)
+            }) {
+                held.push(pending);
+                continue;
+            }
+            state
+                .running
+                .insert(pending.archive.clone(), pending.output_dir);
+            let slot = Slot {
+                queue: self.clone(),
+                archive: pending.archive,
+            };
+            thread::spawn(move || {
+                let _slot = slot;
+                (pending.task)();
+            });
+        }
+        state.pending.extend(held);
+    }
+
+    fn set_max_concurrency(self: &Arc<Self>, max_concurrency: usize) {
+        let mut state = self.0.lock().unwrap();
+        state.max_concurrency = max_concurrency;
+        self.dispatch(state);
+    }
+
+    fn set_priority(&self, archive: &Path, priority: i32) -> Result<(), SevenzError> {
+        let mut state = self.0.lock().unwrap();
+        let mut pending = mem::take(&mut state.pending).into_vec();
+        let found = pending
+            .iter_mut()
+            .find(|p| p.archive == archive)
+            .map(|p| p.priority = priority)
+            .is_some();
+        state.pending = pending.into();
+        match found {
+            true => Ok(()),
+            false => Err(SevenzError::NotQueued(archive.to_path_buf())),
+        }
+    }
+
+    /// Takes the extraction of `job` out of the queue, `None` if it is not waiting.
+    fn remove(&self, job: JobId) -> Option<Pending> {
+        let mut state = self.0.lock().unwrap();
+        let mut pending = mem::take(&mut state.pending).into_vec();
+        let removed = pending
+            .iter()
+            .position(|p| p.job == job)
+            .map(|index| pending.swap_remove(index));
+        state.pending = pending.into();
+        removed
+    }
+}
+
+/// Queues the extraction of `archive` into `output_dir`, see [`ExtractQueue::push`].
+///
+/// The higher `priority` is extracted first.
+pub fn enqueue(
+    archive: PathBuf,
+    output_dir: PathBuf,
+    job: JobId,
+    priority: i32,
+    sender: &mpsc::Sender<(PathBuf, UnzipedArchiveStatus)>,
+    task: impl FnOnce() + Send + 'static,
+) -> Result<(), SevenzError> {
+    QUEUE.push(archive, output_dir, job, priority, sender, Box::new(task))
+}
+
+/// Sets `max_concurrency` from the config, when it is loaded or changed.
+pub fn set_max_concurrency(max_concurrency: usize) {
+    QUEUE.set_max_concurrency(max_concurrency);
+}
+
+/// Changes the priority of an archive still waiting in the queue.
+pub fn set_priority(archive: &Path, priority: i32) -> Result<(), SevenzError> {
+    QUEUE.set_priority(archive, priority)
+}
+
+/// Removes the extraction of `job` if it is still waiting, its task is dropped without running.
+pub fn remove(job: JobId) -> bool {
+    // 在队列的锁外丢弃任务.
+    QUEUE.remove(job).is_some()
+}
+
+#[cfg(test)]
+mod test_queue {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn priority_and_concurrency() {
+        let queue = Arc::new(ExtractQueue::new(1));
+        let (sender, statuses) = mpsc::channel();
+        let (started, order) = mpsc::channel();
+        let (release, blocked) = mpsc::channel::<()>();
+        let blocked = Arc::new(Mutex::new(blocked));
+        let archives = [("x", 0), ("a", 0), ("b", 1), ("c", 0), ("d", 2), ("e", 0)];
+        for (job, (name, priority)) in archives.into_iter().enumerate() {
+            let started = started.clone();
+            let blocked = blocked.clone();
+            let task = Box::new(move || {
+                started.send(name).unwrap();
+                blocked.lock().unwrap().recv().unwrap();
+            });
+            let output_dir = Path::new("out").join(name);
+            queue
+                .push(
+                    name.into(),
+                    output_dir,
+                    job as JobId,
+                    priority,
+                    &sender,
+                    task,
+                )
+                .unwrap();
+        }
+        assert!(matches!(
+            queue.push("a".into(), "out/a2".into(), 9, 0, &sender, Box::new(|| {})),
+            Err(SevenzError::AlreadyQueued(_))
+        ));
+        queue.set_priority(Path::new("c"), 3).unwrap();
+        // 取消的 e 不再运行.
+        assert_eq!(queue.remove(5).map(|p| p.archive), Some("e".into()));
+        assert!(queue.remove(5).is_none());
+        assert!(queue.set_priority(Path::new("e"), 0).is_err());
+        // Queued 和 Job.
+        assert_eq!(statuses.try_iter().count(), 12);
+
+        assert_eq!(order.recv().unwrap(), "x");
+        // 只有 x 在运行, 其余的还在等待, 没有启动其他线程.
+        {
+            let state = queue.0.lock().unwrap();
+            assert_eq!(
+                state.running,
+                HashMap::from([(PathBuf::from("x"), PathBuf::from("out/x"))])
+            );
+            assert_eq!(state.pending.len(), 4);
+        }
+        assert!(order.try_recv().is_err());
+        queue.set_max_concurrency(2);
+        assert_eq!(order.recv().unwrap(), "c");
+        // 每结束一个才开始下一个.
+        for name in ["d", "b", "a"] {
+            release.send(()).unwrap();
+            assert_eq!(order.recv().unwrap(), name);
+        }
+        for _ in 0..2 {
+            release.send(()).unwrap();
+        }
+    }
+
+    #[test]
+    fn same_output_dir() {
+        let queue = Arc::new(ExtractQueue::new(2));
+        let (sender, _statuses) = mpsc::channel();
+        let (started, order) = mpsc::channel();
+        let mut releases = HashMap::new();
+        let archives = [
+            ("a.zip", "out/a"),
+            ("a.7z", "out/a"),
+            ("sub.zip", "out/a/sub"),
+            ("b.zip", "out/b"),
+        ];
+        for (job, (name, output_dir)) in archives.into_iter().enumerate() {
+            let (release, blocked) = mpsc::channel::<()>();
+            releases.insert(name, release);
+            let started = started.clone();
+            let task = Box::new(move || {
+                started.send(name).unwrap();
+                blocked.recv().unwrap();
+            });
+            queue
+                .push(
+                    name.into(),
+                    output_dir.into(),
+                    job as JobId,
+                    0,
+                    &sender,
+                    task,
+                )
+                .unwrap();
+        }
+        // a.7z 和 a.zip 解压到同一个文件夹, sub.zip 解压到它里面, 都要等待.
+        assert_eq!(order.recv().unwrap(), "a.zip");
+        assert_eq!(order.recv().unwrap(), "b.zip");
+        assert!(order.recv_timeout(Duration::from_millis(100)).is_err());
+        releases["a.zip"].send(()).unwrap();
+        assert_eq!(order.recv().unwrap(), "a.7z");
+        releases["b.zip"].send(()).unwrap();
+        assert!(order.recv_timeout(Duration::from_millis(100)).is_err());
+        releases["a.7z"].send(()).unwrap();
+        assert_eq!(order.recv().unwrap(), "sub.zip");
+        releases["sub.zip"].send(()).unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use lru::LruCache;
+
+use super::{
+    error::SevenzError,
+    fs_tree::{sort::SortOptions, ArchiveContents},
+    show_archive_content, Archive,
+};
+use crate::config;
+
+/// Number of listings kept.
+const SESSION_CAPACITY: usize = 16;
+
+/// The listings sent to the frontend, kept for loading children and searching without listing
+/// the archive again.
+///
+/// Managed by Tauri, the least recently used listing is dropped first.
+pub struct ListingSessions(Mutex<LruCache<PathBuf, Arc<ArchiveContents>>>);
+
+impl Default for ListingSessions {
+    fn default() -> Self {
+        Self(Mutex::new(LruCache::new(
+            NonZeroUsize::new(SESSION_CAPACITY).unwrap(),
+        )))
+    }
+}
+
+impl ListingSessions {
+    /// Keeps the listing, replacing the previous listing of the same archive.
+    pub fn insert(&self, contents: ArchiveContents) -> Arc<ArchiveContents> {
+        let contents = Arc::new(contents);
+        let mut sessions = self.0.lock().unwrap();
+        sessions.put(contents.path().to_path_buf(), contents.clone());
+        contents
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Arc<ArchiveContents>> {
+        self.0.lock().unwrap().get(path).cloned()
+    }
+
+    /// Returns the kept listing of the archive, or lists it again if it was dropped.
+    pub fn get_or_list(
+        &self,
+        archive: &Archive,
+        app_config: &config::AppConfig,
+    ) -> Result<Arc<ArchiveContents>, SevenzError> {
+        if let Some(contents) = self.get(&archive.path) {
+            return Ok(contents);
+        }
+        let password = archive.password.clone().unwrap_or_default();
+        let contents = show_archive_content(
+            &archive.path,
+            &password,
+            archive.codepage.clone(),
+            app_config,
+            &SortOptions::default(),
+            &|_| {},
+        )?;
+        Ok(self.insert(contents))
+    }
+}
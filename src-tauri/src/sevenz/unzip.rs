@@ -1,35 +1,126 @@
 use std::{
-    collections::HashSet,
     fs::{self, File, FileTimes},
+    io,
     path::{Component, Path, PathBuf},
-    process::{Command, Stdio},
-    sync::mpsc,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    sync::{mpsc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use notify::{event, RecursiveMode, Watcher};
-use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, FileIdCache};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use tauri_specta::Event;
 use unicode_normalization::UnicodeNormalization;
 use walkdir::WalkDir;
 
-use super::{archives_have_root_dir, sevenz_command, Archive, FilesModified, PasswordCommand};
+use super::{
+    archives_have_root_dir,
+    backend::{archive_backend, ArchiveBackend, ExtractUpdate},
+    error::SevenzError,
+    fs_tree::ArchiveContents,
+    job::{Job, JobId},
+    Archive, FilesModified,
+};
 use crate::config;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Type, Event)]
 pub enum UnzipedArchiveStatus {
     Ok(PathBuf),
+    /// Waiting for a free slot in [`super::queue`].
+    Queued,
+    /// The extraction got an ID to pause or cancel it, see [`super::job`].
+    Job(JobId),
+    /// Started, or resumed after [`UnzipedArchiveStatus::Paused`].
     Running,
+    Paused,
+    /// The extracted files are removed.
+    Cancelled,
+    Progress(ExtractProgress),
     Completed,
+    /// The backend can't open the archive, or every password failed.
+    Failed(String),
+}
+
+/// Entries picked in the tree of an archive, see [`unzip_selected`].
+pub struct Selection {
+    /// Full paths in the archive, none inside another, all entries if empty.
+    paths: Vec<String>,
+    /// Size of the selected files, for the progress.
+    size: Option<u64>,
+    /// The subtree root stripped from the output paths, see [`Selection::subtree`].
+    root: Option<String>,
+}
+
+impl Selection {
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::EntryNotFound)` if a path is not in the archive.
+    pub fn new(contents: &ArchiveContents, mut paths: Vec<String>) -> Result<Self, SevenzError> {
+        paths.iter_mut().for_each(|path| {
+            *path = path.trim_matches('/').to_string();
+        });
+        paths.sort();
+        paths.dedup();
+        // 排序后, 文件夹里的路径紧跟在文件夹后面.
+        let mut selected: Vec<String> = Vec::new();
+        for path in paths {
+            if selected.last().is_some_and(|dir| {
+                path.strip_prefix(dir.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            }) {
+                continue;
+            }
+            selected.push(path);
+        }
+        let mut size = 0;
+        for path in &selected {
+            size += contents
+                .size_of(path)
+                .filter(|_| !path.is_empty())
+                .ok_or_else(|| SevenzError::EntryNotFound(path.clone()))?;
+        }
+        Ok(Selection {
+            paths: selected,
+            size: Some(size),
+            root: None,
+        })
+    }
+
+    /// The whole archive, only the size is known from the listing.
+    pub fn all(contents: &ArchiveContents) -> Self {
+        Selection {
+            paths: Vec::new(),
+            size: contents.size_of(""),
+            root: None,
+        }
+    }
+
+    /// Selects the subtree at `root`, the entries in it are extracted straight into the
+    /// target folder, without the leading folders.
+    ///
+    /// Like `project/src/assets/logo.png` to `{target}/logo.png` if `root` is
+    /// `project/src/assets`. A file `root` is extracted as `{target}/{name}`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err(SevenzError::EntryNotFound)` if `root` is not in the archive.
+    pub fn subtree(contents: &ArchiveContents, root: String) -> Result<Self, SevenzError> {
+        let mut selection = Self::new(contents, vec![root])?;
+        selection.root = selection.paths.first().cloned();
+        Ok(selection)
+    }
 }
 
-pub fn unzip(
+/// Extracts only the `selection` of the archive, all of it if `None`.
+///
+/// The entries keep their paths in the archive, and are staged in [`TempTargetDir`] before
+/// being moved to the output folder. `job` is registered when the extraction is queued.
+pub fn unzip_selected(
     archive: Archive,
+    selection: Option<&Selection>,
     target_dir: &PathBuf,
     global_password: &Option<String>,
     app_config: &config::AppConfig,
+    job: &Job,
     sender: &mpsc::Sender<(PathBuf, UnzipedArchiveStatus)>,
 ) {
     let mut changed = false;
@@ -57,162 +148,141 @@ pub fn unzip(
         target_dir
     };
 
-    sender
-        .send((archive.path.to_owned(), UnzipedArchiveStatus::Running))
-        .unwrap();
+    let backend = match archive_backend(&archive.path) {
+        Ok(backend) => backend,
+        Err(err) => {
+            sender
+                .send((
+                    archive.path.to_owned(),
+                    UnzipedArchiveStatus::Failed(err.to_string()),
+                ))
+                .unwrap();
+            return;
+        }
+    };
+
+    job.send_started();
 
     let password = match archive.password {
         None => global_password.to_owned(),
         _ => archive.password.clone(),
     };
-    if !sevenz_extract(&archive, target_dir, password, sender) {
-        // TODO: password error
+    let mut result = extract(
+        backend.as_ref(),
+        &archive,
+        selection,
+        target_dir,
+        password,
+        job,
+        sender,
+    );
+    if result.is_err() {
         for password in app_config.passwords() {
-            if !sevenz_extract(&archive, target_dir, password.into(), sender) {
-                continue;
+            if job.is_cancelled() {
+                break;
             }
-        }
-    }
-}
-
-const EXTRACT_COMMAND_ARGS: [&str; 3] = ["x", "-aou", "-sccUTF-8"];
-const TEST_COMMAND_ARGS: [&str; 2] = ["t", "-sccUTF-8"];
-
-struct ArchiveCount {
-    folder: usize,
-    file: usize,
-}
-
-const FOLDERS_LINE: &str = "Folders: ";
-const FILES_LINE: &str = "Files: ";
-
-fn sevenz_test(archive: &Archive, password: Option<String>) -> Option<ArchiveCount> {
-    let mut command = sevenz_command().unwrap();
-    command.args(TEST_COMMAND_ARGS);
-    if let Some(password) = password.filter(|p| !p.is_empty()) {
-        command.set_password(&password);
-    }
-    command.arg(&archive.path);
-    let output = command.output().unwrap();
-    if matches!(output.status.code(), Some(0)) {
-        let mut archive_count = ArchiveCount { folder: 0, file: 0 };
-        let output = String::from_utf8_lossy(&output.stdout);
-        output.lines().for_each(|line| {
-            if line.starts_with(FOLDERS_LINE) {
-                archive_count.folder = line.replace(FOLDERS_LINE, "").parse().unwrap();
-            } else if line.starts_with(FILES_LINE) {
-                archive_count.file = line.replace(FILES_LINE, "").parse().unwrap();
+            result = extract(
+                backend.as_ref(),
+                &archive,
+                selection,
+                target_dir,
+                password.into(),
+                job,
+                sender,
+            );
+            if result.is_ok() {
+                break;
             }
-        });
-        // 只有一个文件时, 7z test 不输出 Folders 和 Files.
-        if archive_count.folder == 0 && archive_count.file == 0 {
-            archive_count.file = 1;
         }
-        return Some(archive_count);
     }
-    None
+    let status = match result {
+        _ if job.is_cancelled() => UnzipedArchiveStatus::Cancelled,
+        Ok(()) => return,
+        Err(err) => UnzipedArchiveStatus::Failed(err.to_string()),
+    };
+    sender.send((archive.path.to_owned(), status)).unwrap();
 }
 
-fn sevenz_extract(
+fn extract(
+    backend: &dyn ArchiveBackend,
     archive: &Archive,
-    target_dir: &PathBuf,
+    selection: Option<&Selection>,
+    target_dir: &Path,
     password: Option<String>,
+    job: &Job,
     sender: &mpsc::Sender<(PathBuf, UnzipedArchiveStatus)>,
-) -> bool {
-    let Some(archive_count) = sevenz_test(archive, password.to_owned()) else {
-        return false;
-    };
-    let mut command = sevenz_command().unwrap();
-    command.args(EXTRACT_COMMAND_ARGS);
-
-    let temp_dir = TempTargetDir::new(archive.path.to_owned(), target_dir.to_owned());
-    command.output_dir_arg(&temp_dir);
-
-    if let Some(password) = password.filter(|p| !p.is_empty()) {
-        command.set_password(&password);
+) -> Result<(), SevenzError> {
+    if job.is_cancelled() {
+        return Err(SevenzError::Cancelled);
     }
+    let password = password.as_deref();
+    let includes = selection.map_or(&[][..], |selection| selection.paths.as_slice());
 
-    if let Some(mcp) = &archive.codepage {
-        println!("mcp: {}", mcp);
-        command.arg(mcp.to_string());
+    let mut temp_dir = TempTargetDir::new(archive.path.to_owned(), target_dir.to_owned());
+    if let Some(root) = selection.and_then(|selection| selection.root.as_deref()) {
+        temp_dir.rebase(root);
     }
 
-    command.arg(&archive.path);
-
-    let (_watcher, rx) = temp_dir.watcher();
-
-    let mut child = command
-        .stdin(Stdio::null()) // 阻止输入密码.
-        .stdout(Stdio::null())
-        .spawn()
-        .inspect_err(|_| {
+    let (updates_sender, updates) = mpsc::channel();
+    match backend.extract(
+        archive,
+        &temp_dir.output_path(),
+        password,
+        includes,
+        updates_sender,
+    ) {
+        Ok(process) => job.attach(process),
+        Err(err) => {
             temp_dir.delete();
-        })
-        .expect("7z extract failed"); // PANIC!
-
-    let mut watcher_floder_paths = HashSet::new();
-    let mut watcher_file_paths = HashSet::new();
-    loop {
-        match child.try_wait() {
-            Ok(Some(status)) if !status.success() => {
-                break;
-            }
-            // zip maybe lower folder.
-            Ok(Some(_))
-                if watcher_floder_paths.len() >= archive_count.folder
-                    && watcher_file_paths.len() == archive_count.file =>
-            {
-                break;
-            }
-            Ok(_) => (),
-            Err(_) => {
-                break;
-            }
+            return Err(err);
         }
+    };
 
-        match rx.recv_timeout(Duration::from_millis(1000)) {
-            Ok(Ok(res)) => {
-                for event in res {
-                    let create_kind = match event.kind {
-                        event::EventKind::Create(create_kind) => create_kind,
-                        _ => continue,
-                    };
-                    let path = event.paths.first().unwrap();
-                    if *path == temp_dir.path {
-                        continue;
-                    }
-                    if watcher_floder_paths.contains(path) || watcher_file_paths.contains(path) {
-                        continue;
-                    }
-                    match match create_kind {
-                        event::CreateKind::Folder => true,
-                        event::CreateKind::File => false,
-                        event::CreateKind::Any => path.is_dir(),
-                        _ => true,
-                    } {
-                        true => &mut watcher_floder_paths,
-                        false => &mut watcher_file_paths,
+    let send = |status| {
+        sender.send((archive.path.clone(), status)).unwrap();
+    };
+    let mut progress = ProgressTracker::new(selection.and_then(|selection| selection.size));
+    let mut entries_path = None;
+    // 解压结束时 updates 的发送端会被丢弃.
+    loop {
+        match updates.recv_timeout(PROGRESS_INTERVAL) {
+            Ok(ExtractUpdate::Entry(name)) => {
+                if entries_path.is_none() {
+                    entries_path = temp_dir.entries_path();
+                }
+                if let Some(entries_path) = &entries_path {
+                    let path = entries_path.join(name.trim_end_matches(['/', '\\']));
+                    if temp_dir.is_staged(&path) {
+                        send(UnzipedArchiveStatus::Ok(temp_dir.relative_path(&path)));
                     }
-                    .insert(path.to_owned());
-                    sender
-                        .send((
-                            archive.path.clone(),
-                            UnzipedArchiveStatus::Ok(temp_dir.relative_path(path)),
-                        ))
-                        .unwrap();
                 }
+                progress.file = Some(name);
             }
-            _ => continue,
-        };
+            Ok(ExtractUpdate::Percent(percent)) => progress.set_percent(percent),
+            Ok(ExtractUpdate::Bytes(bytes)) => progress.set_bytes(bytes),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        if let Some(status) = progress.next() {
+            send(UnzipedArchiveStatus::Progress(status));
+        }
     }
 
-    let output = child.wait_with_output().unwrap();
-    if output.status.success() {
+    let result = match job.wait() {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(SevenzError::ExtractFailed),
+        Err(err) => Err(err
+            .downcast::<SevenzError>()
+            .unwrap_or_else(SevenzError::from)),
+    };
+    job.detach();
+    if result.is_ok() {
         let _actual_path = temp_dir.remove();
 
-        sender
-            .send((archive.path.to_owned(), UnzipedArchiveStatus::Completed))
-            .unwrap();
+        if let Some(status) = progress.finish() {
+            send(UnzipedArchiveStatus::Progress(status));
+        }
+        send(UnzipedArchiveStatus::Completed);
         // if let Some(actual_path) = actual_path {
         //     let actual_dir = TempTargetDir {
         //         path: actual_path,
@@ -225,11 +295,116 @@ fn sevenz_extract(
         //         tokio::spawn(async { water_actual_dir(actual_dir, modified).await });
         //     }
         // }
-        true
     } else {
-        println!("{:?} {}", output, output.status.code().unwrap());
         temp_dir.delete();
-        false
+    }
+    result
+}
+
+/// How often [`UnzipedArchiveStatus::Progress`] is sent at most.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Progress of an extraction, sent with [`UnzipedArchiveStatus::Progress`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractProgress {
+    /// `None` if neither the backend nor the listing knows the total.
+    pub percent: Option<f64>,
+    /// Bytes extracted so far.
+    #[specta(type = f64)]
+    pub done: u64,
+    /// Size of the extracted files, known from the listing.
+    #[specta(type = Option<f64>)]
+    pub total: Option<u64>,
+    /// The entry being extracted, its full path in the archive.
+    pub file: Option<String>,
+    /// Bytes per second since the start.
+    pub throughput: f64,
+    /// Estimated seconds left.
+    pub eta: Option<f64>,
+}
+
+/// Turns the [`ExtractUpdate`]s of the backend into [`ExtractProgress`].
+///
+/// 7z reports only the percent and the built-in backends only the bytes, the other one is
+/// derived from the total.
+struct ProgressTracker {
+    total: Option<u64>,
+    percent: Option<f64>,
+    done: u64,
+    file: Option<String>,
+    started: Instant,
+    sent: Option<Instant>,
+}
+
+impl ProgressTracker {
+    fn new(total: Option<u64>) -> Self {
+        ProgressTracker {
+            total,
+            percent: None,
+            done: 0,
+            file: None,
+            started: Instant::now(),
+            sent: None,
+        }
+    }
+
+    fn set_percent(&mut self, percent: f64) {
+        self.percent = Some(percent);
+        if let Some(total) = self.total {
+            self.done = (total as f64 * percent / 100.0) as u64;
+        }
+    }
+
+    fn set_bytes(&mut self, bytes: u64) {
+        self.done = bytes;
+        self.percent = self
+            .total
+            .filter(|&total| total > 0)
+            .map(|total| (bytes as f64 / total as f64 * 100.0).min(100.0));
+    }
+
+    /// Returns the progress if it hasn't been sent for [`PROGRESS_INTERVAL`].
+    fn next(&mut self) -> Option<ExtractProgress> {
+        if self
+            .sent
+            .is_some_and(|sent| sent.elapsed() < PROGRESS_INTERVAL)
+        {
+            return None;
+        }
+        self.sent = Some(Instant::now());
+        Some(self.progress())
+    }
+
+    /// Returns the completed progress, if any progress was sent.
+    fn finish(&mut self) -> Option<ExtractProgress> {
+        self.sent?;
+        if let Some(total) = self.total {
+            self.set_bytes(total.max(self.done));
+        }
+        self.percent = Some(100.0);
+        Some(self.progress())
+    }
+
+    fn progress(&self) -> ExtractProgress {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let throughput = if elapsed > 0.0 {
+            self.done as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = self
+            .total
+            .filter(|_| throughput > 0.0)
+            .map(|total| total.saturating_sub(self.done) as f64 / throughput);
+        ExtractProgress {
+            percent: self.percent,
+            done: self.done,
+            total: self.total,
+            file: self.file.clone(),
+            throughput,
+            eta,
+        }
     }
 }
 
@@ -249,23 +424,26 @@ async fn water_actual_dir(target_dir: TempTargetDir, files_modified: FilesModifi
             let archive_modified = archive_modified.unix_timestamp_nanos();
             let system_time =
                 UNIX_EPOCH + Duration::from_nanos((archive_modified as u128).try_into().unwrap());
-            let filetimes = FileTimes::new();
+            // set_* 返回新的 FileTimes, 不会修改原值.
+            let mut filetimes = FileTimes::new();
             let mut changed = false;
             if let Ok(modified) = metadata.modified() {
                 println!("system time: {:?} modified: {:?}", system_time, modified);
                 if modified > system_time {
-                    filetimes.set_modified(system_time);
+                    filetimes = filetimes.set_modified(system_time);
                     changed = true;
                 }
             };
             if let Ok(accessed) = metadata.accessed() {
                 println!("system time: {:?} accessed: {:?}", system_time, accessed);
                 if accessed > system_time {
-                    filetimes.set_accessed(system_time);
+                    filetimes = filetimes.set_accessed(system_time);
                     changed = true;
                 }
             };
-            if cfg!(any(target_os = "windows", target_os = "macos")) {
+            // set_created 只在 Windows 和 macOS 上存在, cfg! 不能排除其他平台的编译.
+            #[cfg(any(target_os = "windows", target_os = "macos"))]
+            {
                 #[cfg(target_os = "macos")]
                 use std::os::macos::fs::FileTimesExt;
                 #[cfg(target_os = "windows")]
@@ -274,7 +452,7 @@ async fn water_actual_dir(target_dir: TempTargetDir, files_modified: FilesModifi
                 if let Ok(created) = metadata.created() {
                     println!("system time: {:?} created: {:?}", system_time, created);
                     if created > system_time {
-                        filetimes.set_created(system_time);
+                        filetimes = filetimes.set_created(system_time);
                         changed = true;
                     }
                 }
@@ -289,56 +467,116 @@ async fn water_actual_dir(target_dir: TempTargetDir, files_modified: FilesModifi
             }
         }
     }
-    let (_watcher, rx) = target_dir.watcher();
-    if let Ok(Ok(res)) = rx.recv() {
-        for event in res {
-            println!("{:?}", event);
-        }
-    };
+}
+
+/// The folder the archive is extracted into, the folder of the archive if `target_dir` is empty.
+pub(super) fn target_base_dir(path: &Path, target_dir: &Path) -> PathBuf {
+    // TODO: 支持相对压缩文件的路径.
+    if *target_dir != PathBuf::new() {
+        target_dir.to_path_buf()
+    } else if path.is_file() {
+        path.parent().unwrap().to_path_buf()
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// The folder the extraction of `archive` ends up in, the queue doesn't run two extractions
+/// into the same folder at once.
+///
+/// Archives without a root folder get a folder named after them. The name of the root folder
+/// is not known before extracting, so the other archives and the subtrees moved straight into
+/// the target folder take the whole folder.
+pub fn output_dir(archive: &Path, target_dir: &Path, selection: Option<&Selection>) -> PathBuf {
+    let mut output_dir = target_base_dir(archive, target_dir);
+    let has_root_dir = *archives_have_root_dir().get(archive).unwrap_or(&false);
+    if !has_root_dir && selection.is_none_or(|selection| selection.root.is_none()) {
+        output_dir.push(archive.file_stem().unwrap_or_default());
+    }
+    output_dir
 }
 
 struct TempTargetDir {
     pub path: PathBuf,
     has_root_dir: bool,
+    /// The subtree root in the archive, its contents are moved to the target folder.
+    root: Option<PathBuf>,
 }
 
 impl TempTargetDir {
     fn new(path: PathBuf, target_dir: PathBuf) -> TempTargetDir {
-        // TODO: 支持相对压缩文件的路径.
-        let mut temp_dir: PathBuf = if *target_dir != PathBuf::new() {
-            target_dir.to_path_buf()
-        } else if path.is_file() {
-            path.parent().unwrap().to_path_buf()
-        } else {
-            path.to_path_buf()
+        let base_dir = target_base_dir(&path, &target_dir);
+        fs::create_dir_all(&base_dir).unwrap();
+        let mut millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        // 同时开始的解压会得到同一个时间, 创建成功的名称才属于自己.
+        let temp_dir = loop {
+            let temp_dir = base_dir.join(format!("_EZ{}", millis));
+            match fs::create_dir(&temp_dir) {
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => millis += 1,
+                result => {
+                    result.unwrap();
+                    break temp_dir;
+                }
+            }
         };
-        let temp = format!(
-            "_EZ{}",
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis()
-        );
-        temp_dir.push(temp);
-
-        if !temp_dir.exists() {
-            std::fs::create_dir_all(&temp_dir).unwrap();
-        }
 
         TempTargetDir {
             path: temp_dir,
             has_root_dir: *archives_have_root_dir().get(&path).unwrap_or(&false),
+            root: None,
         }
     }
 
+    /// Extracts the entries with their full paths, and moves only the contents of `root`.
+    fn rebase(&mut self, root: &str) {
+        self.has_root_dir = true;
+        self.root = Some(root.split('/').collect());
+    }
+
+    /// The folder whose contents are moved to the target folder.
+    fn staged_path(&self) -> PathBuf {
+        match &self.root {
+            Some(root) => self.path.join(root),
+            None => self.path.to_owned(),
+        }
+    }
+
+    /// Whether the path is moved to the target folder, the folders containing the subtree
+    /// root are not.
+    fn is_staged(&self, path: &Path) -> bool {
+        match &self.root {
+            Some(root) if self.path.join(root) == path => !path.is_dir(),
+            _ => path != self.staged_path() && path.starts_with(self.staged_path()),
+        }
+    }
+
+    /// The directory the archive is extracted to.
+    ///
+    /// Archives without a root directory are extracted into a folder named after the archive,
+    /// with the `-o{dir}/*` option of 7z.
     fn output_path(&self) -> PathBuf {
-        if self.has_root_dir {
-            self.path.to_owned()
-        } else {
-            let mut path = self.path.to_owned();
+        let mut path = self.path.to_owned();
+        if !self.has_root_dir {
             path.push("*");
-            path
         }
+        path
+    }
+
+    /// The folder the entries are extracted to, `None` until the backend creates it.
+    ///
+    /// 7z names the folder of `*` itself, e.g. `a.tar` for `a.tgz`.
+    fn entries_path(&self) -> Option<PathBuf> {
+        if self.has_root_dir {
+            return Some(self.path.to_owned());
+        }
+        fs::read_dir(&self.path)
+            .ok()?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .find(|path| path.is_dir())
     }
 
     fn delete(&self) -> bool {
@@ -346,12 +584,22 @@ impl TempTargetDir {
     }
 
     fn remove(&self) -> Option<PathBuf> {
+        // 同时解压到一个目标文件夹时, 避免选中同一个不重复的名称.
+        static MOVE_LOCK: Mutex<()> = Mutex::new(());
+        let _lock = MOVE_LOCK.lock().unwrap();
         let temp_dir = &self.path;
         let parent_folder = temp_dir.parent().unwrap();
         let mut result = None;
-        for entry in fs::read_dir(temp_dir).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
+        let staged_path = self.staged_path();
+        let paths = if staged_path.is_dir() {
+            fs::read_dir(&staged_path)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect()
+        } else {
+            vec![staged_path]
+        };
+        for path in paths {
             let sub_name = path.file_name().unwrap();
             // 如果文件夹名重复, 需要加后缀.
             let mut sub_path = parent_folder.join(sub_name);
@@ -372,22 +620,14 @@ impl TempTargetDir {
         result
     }
 
-    fn watcher(
-        &self,
-    ) -> (
-        Debouncer<impl Watcher, impl FileIdCache>,
-        mpsc::Receiver<DebounceEventResult>,
-    ) {
-        let (tx, rx) = mpsc::channel();
-        let mut debouncer = new_debouncer(Duration::from_millis(1000), None, tx).unwrap();
-        debouncer
-            .watch(&self.path, RecursiveMode::Recursive)
-            .unwrap();
-        (debouncer, rx)
-    }
-
+    /// The path relative to the target folder.
     fn relative_path(&self, path: &PathBuf) -> PathBuf {
-        _relative_path(&self.path, path)
+        match &self.root {
+            // 文件作为根时, 移动到目标文件夹的只有它自己.
+            Some(_) if !self.staged_path().is_dir() => path.file_name().unwrap_or_default().into(),
+            Some(_) => _relative_path(&self.staged_path(), path),
+            None => _relative_path(&self.path, path),
+        }
     }
 
     fn archive_relative_path(&self, path: &PathBuf) -> PathBuf {
@@ -415,15 +655,119 @@ fn _relative_path(dest: &Path, path: &PathBuf) -> PathBuf {
     }
 }
 
-trait SevenzOutput<T> {
-    fn output_dir_arg(&mut self, dir: T) -> &mut Self;
-}
+#[cfg(test)]
+mod test_unzip {
+    use std::io::{Cursor, Write};
 
-impl SevenzOutput<&TempTargetDir> for Command {
-    fn output_dir_arg(&mut self, dir: &TempTargetDir) -> &mut Self {
-        let output_dir = format!(r#"-o{}"#, dir.output_path().to_string_lossy());
-        {
-            self.arg(output_dir)
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+    use crate::sevenz::{backend::zip::ZipBackend, job};
+
+    fn write_zip(path: &Path, files: &[(String, &str)]) {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(name.as_str(), SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        fs::write(path, writer.finish().unwrap().into_inner()).unwrap();
+    }
+
+    #[test]
+    fn unzip_subtree() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-unzip-{}", std::process::id()));
+        let target_dir = dir.join("target");
+        fs::create_dir_all(target_dir.join("assets")).unwrap();
+        fs::write(target_dir.join("logo.png"), "old").unwrap();
+
+        let archive_path = dir.join("project.zip");
+        write_zip(
+            &archive_path,
+            &[
+                ("project/readme.md".to_string(), "readme"),
+                ("project/src/assets/logo.png".to_string(), "logo"),
+                ("project/src/assets/icons/a.svg".to_string(), "a"),
+            ],
+        );
+
+        let mut contents = ZipBackend.list(&archive_path, "", None, &|_| {}).unwrap();
+        contents.set_has_root_dir();
+        let selection = Selection::subtree(&contents, "project/src/assets/".to_string()).unwrap();
+        assert_eq!(selection.size, Some(5));
+        assert!(Selection::subtree(&contents, "project/none".to_string()).is_err());
+
+        let archive = Archive {
+            path: archive_path,
+            password: None,
+            codepage: None,
+        };
+        let (tx, rx) = mpsc::channel();
+        let job = Job::register(archive.path.clone(), &tx);
+        extract(
+            &ZipBackend,
+            &archive,
+            Some(&selection),
+            &target_dir,
+            None,
+            &job,
+            &tx,
+        )
+        .unwrap();
+        drop((job, tx));
+
+        assert_eq!(
+            fs::read_to_string(target_dir.join("logo.png.1")).unwrap(),
+            "logo"
+        );
+        assert!(target_dir.join("icons/a.svg").exists());
+        assert!(!target_dir.join("project").exists());
+        for (_, status) in rx {
+            if let UnzipedArchiveStatus::Ok(path) = status {
+                assert!(!path.starts_with("project"), "{path:?}");
+            }
         }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cancel_paused_job() {
+        let dir = std::env::temp_dir().join(format!("e-zip-test-job-{}", std::process::id()));
+        let target_dir = dir.join("target");
+        fs::create_dir_all(&target_dir).unwrap();
+        let archive_path = dir.join("many.zip");
+        let files = (0..200)
+            .map(|i| (format!("many/{i}.txt"), "text"))
+            .collect::<Vec<_>>();
+        write_zip(&archive_path, &files);
+        let archive = Archive {
+            path: archive_path,
+            password: None,
+            codepage: None,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let job = Job::register(archive.path.clone(), &tx);
+        let id = job.id();
+        job::pause(id).unwrap();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            job::cancel(id).unwrap();
+        });
+        assert!(extract(&ZipBackend, &archive, None, &target_dir, None, &job, &tx).is_err());
+        canceller.join().unwrap();
+        assert!(job.is_cancelled());
+        assert!(matches!(job::resume(id), Ok(())));
+        drop((job, tx));
+
+        assert!(matches!(job::pause(id), Err(SevenzError::JobNotFound(_))));
+        let statuses = rx.into_iter().map(|(_, status)| status).collect::<Vec<_>>();
+        assert!(matches!(statuses[0], UnzipedArchiveStatus::Paused));
+        // 临时文件夹已删除.
+        assert_eq!(fs::read_dir(&target_dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
     }
 }
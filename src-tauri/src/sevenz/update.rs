@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+use specta::Type;
+use tauri_plugin_http::reqwest;
+
+use super::{
+    detect::{self, version_key, SevenzCandidate},
+    error::SevenzError,
+    installer::{self, InstallProgress, InstallSource, Release},
+};
+
+/// The page listing the official releases, used when no mirror is configured.
+const DOWNLOAD_PAGE: &str = "https://7-zip.org/download.html";
+
+/// Result of [`check_update`].
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheck {
+    /// The version of the 7z command in use.
+    pub installed: String,
+    /// The newest version on the download page or mirror, it may not be verified yet.
+    pub latest: Option<String>,
+    /// Whether `latest` is newer than `installed`.
+    pub update_available: bool,
+    /// The newest version the installer accepts, see `7z-manifest.toml`.
+    pub verified: Option<String>,
+    /// Whether `verified` is newer than `installed`, so [`upgrade_private`] can install it.
+    pub installable: bool,
+}
+
+/// Compares `installed` with the newest release for this platform.
+///
+/// Without a mirror the official download page is read, otherwise the mirror URL itself, which
+/// is expected to list the release files like a directory index.
+pub async fn check_update(
+    installed: &str,
+    mirror: Option<&str>,
+) -> Result<UpdateCheck, SevenzError> {
+    let page = mirror.unwrap_or(DOWNLOAD_PAGE);
+    let html = reqwest::get(page).await?.error_for_status()?.text().await?;
+    let latest = latest_version(&html, installer::platform_suffix());
+    let verified = installer::manifest()
+        .newest_release()
+        .and_then(Release::version);
+    Ok(UpdateCheck {
+        installed: installed.to_string(),
+        update_available: is_newer(latest.as_deref(), installed),
+        latest,
+        installable: is_newer(verified.as_deref(), installed),
+        verified,
+    })
+}
+
+/// Upgrades the private 7z command in `config_dir` to the newest verified release.
+///
+/// The replaced copy is kept as a fallback, see [`detect::previous_command_path`].
+///
+/// # Errors
+///
+/// Returns `Err(SevenzError::InstallError)` if the private copy is already up to date.
+pub async fn upgrade_private<F>(
+    config_dir: &Path,
+    mirror: Option<String>,
+    on_progress: F,
+) -> Result<SevenzCandidate, SevenzError>
+where
+    F: Fn(InstallProgress) + Send + Sync,
+{
+    let verified = installer::manifest()
+        .newest_release()
+        .and_then(Release::version);
+    let private = SevenzCandidate::probe(detect::private_command_path(config_dir));
+    if let (Some(private), Some(verified)) = (&private, &verified)
+        && version_key(verified) <= version_key(&private.version)
+    {
+        return Err(SevenzError::InstallError(format!(
+            "已是最新的已校验版本: {}",
+            private.version
+        )));
+    }
+    super::download_7z(config_dir, InstallSource::Mirror(mirror), on_progress).await
+}
+
+fn is_newer(version: Option<&str>, installed: &str) -> bool {
+    version.is_some_and(|version| version_key(version) > version_key(installed))
+}
+
+/// The newest version of the release files for `suffix` linked in `html`.
+fn latest_version(html: &str, suffix: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"7z\d{{4}}-{}", regex::escape(suffix))).unwrap();
+    re.find_iter(html)
+        .filter_map(|file| installer::release_version(file.as_str()))
+        .max_by_key(|version| version_key(version))
+}
+
+#[cfg(test)]
+mod test_update {
+    use super::*;
+
+    #[test]
+    fn latest() {
+        let html = r#"
+<TD class="Item" align="center"><A href="a/7z2301-linux-x64.tar.xz">Download</A></TD>
+<TD class="Item" align="center"><A href="a/7z2409-linux-x64.tar.xz">Download</A></TD>
+<TD class="Item" align="center"><A href="a/7z2409-linux-arm64.tar.xz">Download</A></TD>
+<TD class="Item" align="center"><A href="a/7z2408-mac.tar.xz">Download</A></TD>
+"#;
+        assert_eq!(
+            latest_version(html, "linux-x64.tar.xz").as_deref(),
+            Some("24.09")
+        );
+        assert_eq!(latest_version(html, "mac.tar.xz").as_deref(), Some("24.08"));
+        assert_eq!(latest_version(html, "extra.7z"), None);
+    }
+
+    #[test]
+    fn newer() {
+        assert!(is_newer(Some("24.09"), "23.01"));
+        assert!(!is_newer(Some("24.09"), "24.09"));
+        // 页面上没有找到版本, 或者没有已校验的版本.
+        assert!(!is_newer(None, "23.01"));
+    }
+}
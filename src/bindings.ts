@@ -5,7 +5,7 @@
 
 
 export const commands = {
-async check7zVersion() : Promise<Result<string, SevenzError>> {
+async check7zVersion() : Promise<Result<SevenZipInfo, SevenzError>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("check_7z_version") };
 } catch (e) {
@@ -13,17 +13,125 @@ async check7zVersion() : Promise<Result<string, SevenzError>> {
     else return { status: "error", error: e  as any };
 }
 },
-async download7z() : Promise<Result<null, SevenzError>> {
+async list7zCandidates() : Promise<SevenzCandidate[]> {
+    return await TAURI_INVOKE("list_7z_candidates");
+},
+/**
+ * Switches the 7z executable without restarting, `None` goes back to auto-detection.
+ */
+async select7z(path: string | null) : Promise<Result<SevenzCandidate | null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("select_7z", { path }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Installs 7-Zip from the configured mirror, or from `archive` if given.
+ */
+async download7z(archive: string | null, onProgress: TAURI_CHANNEL<InstallProgress>) : Promise<Result<SevenzCandidate, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("download_7z", { archive, onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Cancels `download_7z`, the partial download is removed.
+ */
+async cancelDownload7z() : Promise<void> {
+    await TAURI_INVOKE("cancel_download_7z");
+},
+/**
+ * Compares the 7z command in use with the newest release on the configured mirror.
+ */
+async check7zUpdate() : Promise<Result<UpdateCheck, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("check_7z_update") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Upgrades the private 7z command, the replaced copy is kept as a fallback.
+ */
+async upgrade7z(onProgress: TAURI_CHANNEL<InstallProgress>) : Promise<Result<SevenzCandidate, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("upgrade_7z", { onProgress }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async unzipArchives(archives: Archive[], targetDir: string, globalPassword: string | null, priority: number | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unzip_archives", { archives, targetDir, globalPassword, priority }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Extracts only `paths` of the archive, files or folders picked in its tree.
+ */
+async unzipSelected(archive: Archive, paths: string[], targetDir: string, globalPassword: string | null) : Promise<Result<null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unzip_selected", { archive, paths, targetDir, globalPassword }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Extracts the folder or file at `root` of the archive straight into `target_dir`,
+ * without the folders containing it.
+ * 
+ * The paths of [`sevenz::unzip::UnzipedArchiveStatus::Ok`] are relative to `target_dir`.
+ */
+async unzipSubtree(archive: Archive, root: string, targetDir: string, globalPassword: string | null) : Promise<Result<null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("unzip_subtree", { archive, root, targetDir, globalPassword }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Cancels the extraction, see [`sevenz::job`].
+ */
+async cancelJob(id: number) : Promise<Result<null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("cancel_job", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async pauseJob(id: number) : Promise<Result<null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pause_job", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async resumeJob(id: number) : Promise<Result<null, SevenzError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("download_7z") };
+    return { status: "ok", data: await TAURI_INVOKE("resume_job", { id }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async unzipArchives(archives: Archive[], targetDir: string, globalPassword: string | null) : Promise<Result<null, string>> {
+/**
+ * Changes the priority of an archive waiting in the extraction queue.
+ */
+async setUnzipPriority(archivePath: string, priority: number) : Promise<Result<null, SevenzError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("unzip_archives", { archives, targetDir, globalPassword }) };
+    return { status: "ok", data: await TAURI_INVOKE("set_unzip_priority", { archivePath, priority }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -32,17 +140,83 @@ async unzipArchives(archives: Archive[], targetDir: string, globalPassword: stri
 async deleteArchives(paths: string[], onEvent: TAURI_CHANNEL<DeletedArchiveEvent>) : Promise<void> {
     await TAURI_INVOKE("delete_archives", { paths, onEvent });
 },
-async showArchivesContents(paths: string[], password: string) : Promise<Result<null, null>> {
+async showArchivesContents(paths: string[], password: string, depth: number | null, sort: SortOptions | null) : Promise<Result<null, null>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("show_archives_contents", { paths, password, depth, sort }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async refreshArchiveContents(archive: Archive, password: string, depth: number | null, sort: SortOptions | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("refresh_archive_contents", { archive, password, depth, sort }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Tests the archive without extracting it, with the passwords of the config if needed.
+ */
+async testArchive(archive: Archive) : Promise<Result<null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("test_archive", { archive }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Finds entries in an archive by name or path, see [`SearchQuery`].
+ */
+async searchArchiveContents(archive: Archive, query: SearchQuery) : Promise<Result<SearchMatch[], SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("search_archive_contents", { archive, query }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Returns the node at `node_path` of a listed archive, with `depth` levels of children.
+ */
+async getArchiveChildren(archive: Archive, nodePath: string, depth: number | null) : Promise<Result<{ value: FsNode; children: SpectaNode<FsNode>[]; truncated: boolean }, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_archive_children", { archive, nodePath, depth }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Writes the listings of the archives to `output`, see [`ExportFormat`].
+ */
+async exportArchiveListing(archives: Archive[], format: ExportFormat, output: string) : Promise<Result<null, SevenzError>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_archive_listing", { archives, format, output }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Compares the contents of two archives, usually two versions of the same one.
+ */
+async diffArchives(old: Archive, new: Archive) : Promise<Result<ArchiveDiff, SevenzError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("show_archives_contents", { paths, password }) };
+    return { status: "ok", data: await TAURI_INVOKE("diff_archives", { old, new }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
 },
-async refreshArchiveContents(archive: Archive, password: string) : Promise<Result<null, string>> {
+/**
+ * Checks which files of the archives are already in the target folder.
+ */
+async checkExtracted(archives: Archive[], targetDir: string, compare: CompareBy) : Promise<Result<ExtractedReport[], SevenzError>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("refresh_archive_contents", { archive, password }) };
+    return { status: "ok", data: await TAURI_INVOKE("check_extracted", { archives, targetDir, compare }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -70,9 +244,11 @@ async updateConfig(appConfig: AppConfig) : Promise<Result<null, string>> {
 
 
 export const events = __makeEvents__<{
+listingProgressEvent: ListingProgressEvent,
 showArchiveContentsEvent: ShowArchiveContentsEvent,
 unzipedArchiveEvent: UnzipedArchiveEvent
 }>({
+listingProgressEvent: "listing-progress-event",
 showArchiveContentsEvent: "show-archive-contents-event",
 unzipedArchiveEvent: "unziped-archive-event"
 })
@@ -83,21 +259,380 @@ unzipedArchiveEvent: "unziped-archive-event"
 
 /** user-defined types **/
 
-export type AppConfig = { target: Target; autoDelete: boolean; passwords: string[] }
+export type AppConfig = { target: Target; autoDelete: boolean; passwords: string[]; sevenzPath: string | null; sevenzMirror: string | null; maxConcurrency: number | null }
 export type Archive = { path: string; password: string | null; codepage: Codepage | null }
-export type ArchiveContents = { path: string; contents: unknown; password: string | null; codepage: Codepage | null; multiVolume: ArchiveMultiVolume | null; hasRootDir: boolean }
+export type ArchiveContents = { path: string; contents: unknown; password: string | null; codepage: Codepage | null; multiVolume: ArchiveMultiVolume | null; hasRootDir: boolean; info: ArchiveInfo; 
+/**
+ * Sizes of all files in the archive.
+ */
+stats: FsStats }
+/**
+ * The changes from an old archive to a new one, see [`ArchiveContents::diff`].
+ */
+export type ArchiveDiff = { added: number; removed: number; modified: number; moved: number; 
+/**
+ * The top level nodes.
+ */
+tree: DiffNode[] }
+/**
+ * Metadata of an entry in the archive, the fields of `7z l -slt`.
+ * 
+ * Fields the archive format doesn't store are `None`.
+ */
+export type ArchiveEntry = { size: number | null; packedSize: number | null; 
+/**
+ * CRC32 in upper case hex, like `3610A686`.
+ */
+crc: string | null; 
+/**
+ * Compression method, like `LZMA2:24` or `Deflate`.
+ */
+method: string | null; encrypted: boolean; created: string | null; accessed: string | null; 
+/**
+ * Attributes as printed by 7z, like `D drwxr-xr-x` or `A -rw-r--r--`.
+ */
+attributes: string | null; comment: string | null; 
+/**
+ * The solid block the entry belongs to.
+ */
+block: number | null }
+/**
+ * Archive-level information, the header block of `7z l -slt` before the entries.
+ */
+export type ArchiveInfo = { 
+/**
+ * The format, like `7z`, `zip` or `Rar5`.
+ */
+archiveType: string | null; physicalSize: number | null; headersSize: number | null; method: string | null; solid: boolean; blocks: number | null; 
+/**
+ * File names can't be listed without the password.
+ */
+encryptedHeaders: boolean; comment: string | null; recoveryRecord: boolean; 
+/**
+ * RAR archives locked against changes.
+ */
+locked: boolean }
 export type ArchiveMultiVolume = { volumes: string[]; actualPath: string }
 export type Codepage = "SHIFT_JIS" | "GB2312" | "BIG5" | "UTF_8" | { other: number }
+export type Collation = "CodePoint" | 
+/**
+ * Chinese characters by their pinyin among the latin letters, ignoring case.
+ */
+"Pinyin"
+/**
+ * How files on disk are compared to the entries, each includes the previous.
+ */
+export type CompareBy = "Size" | "Modified" | 
+/**
+ * CRC32, reads the files.
+ */
+"Hash"
 export type DeletedArchiveEvent = [string, string | null]
-export type Fs = { name: string; modified: string | null; parent: string | null }
+/**
+ * A metadata of an entry that differs between the archives.
+ */
+export type DiffField = "Size" | "Crc" | "Modified"
+/**
+ * A node of the diff tree.
+ * 
+ * Only the changed entries and the folders containing them are in the tree,
+ * `status` is `None` for the unchanged folders.
+ */
+export type DiffNode = { name: string; 
+/**
+ * The full path in the archive, separated by `/`.
+ */
+path: string; isDir: boolean; status: DiffStatus | null; children: DiffNode[] }
+export type DiffStatus = 
+/**
+ * Only in the new archive.
+ */
+{ type: "Added" } | 
+/**
+ * Only in the old archive.
+ */
+{ type: "Removed" } | 
+/**
+ * In both archives with different metadata.
+ */
+{ type: "Modified"; fields: DiffField[] } | 
+/**
+ * The same file under another path, `from` is the path in the old archive.
+ */
+{ type: "Moved"; from: string }
+/**
+ * The file format of [`export_listing`].
+ */
+export type ExportFormat = 
+/**
+ * The trees with all metadata.
+ */
+"Json" | 
+/**
+ * One row per entry.
+ */
+"Csv" | 
+/**
+ * The trees as printed by [`super::FsTree`]'s `Display`, readable as plain text too.
+ */
+"Markdown" | 
+/**
+ * A static page with collapsible folders.
+ */
+"Html"
+/**
+ * Progress of an extraction, sent with [`UnzipedArchiveStatus::Progress`].
+ */
+export type ExtractProgress = { 
+/**
+ * `None` if neither the backend nor the listing knows the total.
+ */
+percent: number | null; 
+/**
+ * Bytes extracted so far.
+ */
+done: number; 
+/**
+ * Size of the extracted files, known from the listing.
+ */
+total: number | null; 
+/**
+ * The entry being extracted, its full path in the archive.
+ */
+file: string | null; 
+/**
+ * Bytes per second since the start.
+ */
+throughput: number; 
+/**
+ * Estimated seconds left.
+ */
+eta: number | null }
+export type ExtractedEntry = { 
+/**
+ * The full path in the archive, separated by `/`.
+ */
+path: string; status: ExtractedStatus }
+/**
+ * The files of an archive found in the target folder, see [`ArchiveContents::check_extracted`].
+ */
+export type ExtractedReport = { archive: string; 
+/**
+ * Where the files of the archive would be extracted to.
+ */
+output: string; identical: number; different: number; missing: number; 
+/**
+ * The files in tree order, folders are not checked.
+ */
+entries: ExtractedEntry[] }
+export type ExtractedStatus = "Identical" | "Different" | "Missing"
+export type Fs = { name: string; modified: string | null; 
+/**
+ * The full path of the parent folder, empty at the top level.
+ */
+parent: string | null; 
+/**
+ * The full path in the archive, separated by `/`.
+ */
+path: string; 
+/**
+ * The path as stored in the archive, before decoding it with the codepage.
+ * 
+ * Only sent to the frontend when it differs from the UTF-8 of `path`, large archives
+ * have many nodes.
+ */
+rawPath?: number[]; 
+/**
+ * Derived from `path`, so it stays the same when the archive is listed again.
+ */
+id: string; entry: ArchiveEntry; stats: FsStats }
 export type FsNode = { type: "None" } | ({ type: "Dir" } & Fs) | ({ type: "File" } & Fs)
+/**
+ * Sizes of a file, or summed over all files below a folder.
+ */
+export type FsStats = { size: number; packedSize: number; files: number; 
+/**
+ * `packed_size / size`, `None` if the size is 0.
+ */
+ratio: number | null }
+/**
+ * A step of the installation.
+ */
+export type InstallPhase = 
+/**
+ * Choosing the release in the manifest.
+ */
+"Resolve" | "Download" | 
+/**
+ * Checking the SHA-256 checksum.
+ */
+"Verify" | "Unpack"
+/**
+ * Progress of the installation, `bytes` of `total` in the current phase.
+ */
+export type InstallProgress = { phase: InstallPhase; bytes: number; total: number | null }
 export type IoError = string
-export type SevenzError = "NotFound7z" | { NeedPassword: string } | { CommandError: string } | { CommandIoError: IoError } | { InvalidUtf8: string } | { UnsupportedFile: string }
+/**
+ * Sent while an archive is being listed, with the number of entries listed so far.
+ */
+export type ListingProgressEvent = { path: string; entries: number }
+export type SearchKind = "File" | "Dir"
+/**
+ * A node matching a [`SearchQuery`].
+ */
+export type SearchMatch = { 
+/**
+ * The full path in the archive, separated by `/`.
+ */
+path: string; 
+/**
+ * The ID of the node, see [`super::Fs`].
+ */
+id: string; 
+/**
+ * The paths of the folders containing the node, outermost first, for expanding them.
+ */
+ancestors: string[]; isDir: boolean; 
+/**
+ * See [`super::Fs`], only sent when it differs from the UTF-8 of `path`.
+ */
+rawPath?: number[] }
+/**
+ * What the search text is.
+ */
+export type SearchPattern = 
+/**
+ * Plain text contained in the name.
+ */
+{ Substring: string } | 
+/**
+ * `*` and `?` don't cross `/`, `**` does.
+ */
+{ Glob: string } | { Regex: string }
+/**
+ * A search in the listed contents of an archive.
+ * 
+ * The filters are optional, only the nodes matching all of them are returned.
+ */
+export type SearchQuery = { pattern: SearchPattern; caseSensitive: boolean; 
+/**
+ * Matches the full path in the archive instead of the name.
+ */
+matchPath: boolean; 
+/**
+ * Size in bytes, the total size of the files for folders.
+ */
+minSize: number | null; maxSize: number | null; modifiedAfter: string | null; modifiedBefore: string | null; kind: SearchKind | null; 
+/**
+ * Stops after this many matches.
+ */
+limit: number | null }
+/**
+ * What a 7z executable is and what it can open, parsed from `7z i`.
+ */
+export type SevenZipInfo = { version: string; 
+/**
+ * `x64`, `arm64`..., or `64-bit` for p7zip.
+ */
+architecture: string | null; 
+/**
+ * `YYYY-MM-DD`.
+ */
+buildDate: string | null; flavor: SevenzFlavor; 
+/**
+ * Archive format names, like `7z`, `zip` or `Rar5`.
+ */
+formats: string[]; 
+/**
+ * Codec names, like `LZMA2`, `Deflate` or `7zAES`.
+ */
+codecs: string[] }
+/**
+ * A 7z executable found on this machine.
+ */
+export type SevenzCandidate = { path: string; version: string; flavor: SevenzFlavor }
+export type SevenzError = "NotFound7z" | { Invalid7z: string } | { NeedPassword: string } | { CommandError: string } | { CommandIoError: IoError } | { InvalidUtf8: string } | { UnsupportedFile: string } | { UnsupportedFormat: [string, string] } | { ArchiveError: string } | { ChecksumMismatch: string } | { InstallError: string } | "Cancelled" | "ExtractFailed" | { InvalidQuery: string } | { EntryNotFound: string } | { JobNotFound: number } | { AlreadyQueued: string } | { NotQueued: string }
+/**
+ * Which 7-Zip distribution an executable belongs to.
+ */
+export type SevenzFlavor = 
+/**
+ * The official 7-Zip (`7z`, `7zz`, `7za`, `7zr`) from 7-zip.org.
+ */
+"SevenZip" | 
+/**
+ * The p7zip port found on older Linux distributions.
+ */
+"P7zip"
 export type ShowArchiveContentsEvent = SpectaResult<ArchiveContents, SevenzError>
+export type SortBy = "Name" | 
+/**
+ * The total size of the files for folders.
+ */
+"Size" | "Modified"
+/**
+ * How the siblings of the tree are ordered.
+ * 
+ * The default sorts by name in code point order, like before the options existed.
+ */
+export type SortOptions = { sortBy: SortBy; foldersFirst: boolean; 
+/**
+ * Compares the digits in names as numbers, `file2` before `file10`.
+ */
+natural: boolean; collation: Collation; descending: boolean }
+export type SpectaNode<T> = { value: T; children: SpectaNode<T>[]; 
+/**
+ * The children were not sent, see [`ArchiveContents::set_depth`].
+ */
+truncated: boolean }
 export type SpectaResult<T, E> = { status: "ok"; data: T } | { status: "error"; error: E }
 export type Target = { dir: string; canInput: boolean }
 export type UnzipedArchiveEvent = [string, UnzipedArchiveStatus]
-export type UnzipedArchiveStatus = { Ok: string } | "Running" | "Completed"
+export type UnzipedArchiveStatus = { Ok: string } | 
+/**
+ * Waiting for a free slot in [`super::queue`].
+ */
+"Queued" | 
+/**
+ * The extraction got an ID to pause or cancel it, see [`super::job`].
+ */
+{ Job: number } | 
+/**
+ * Started, or resumed after [`UnzipedArchiveStatus::Paused`].
+ */
+"Running" | "Paused" | 
+/**
+ * The extracted files are removed.
+ */
+"Cancelled" | { Progress: ExtractProgress } | "Completed" | 
+/**
+ * The backend can't open the archive, or every password failed.
+ */
+{ Failed: string }
+/**
+ * Result of [`check_update`].
+ */
+export type UpdateCheck = { 
+/**
+ * The version of the 7z command in use.
+ */
+installed: string; 
+/**
+ * The newest version on the download page or mirror, it may not be verified yet.
+ */
+latest: string | null; 
+/**
+ * Whether `latest` is newer than `installed`.
+ */
+updateAvailable: boolean; 
+/**
+ * The newest version the installer accepts, see `7z-manifest.toml`.
+ */
+verified: string | null; 
+/**
+ * Whether `verified` is newer than `installed`, so [`upgrade_private`] can install it.
+ */
+installable: boolean }
 
 /** tauri-specta globals **/
 
@@ -13,6 +13,30 @@ export const ArchiveContent: Component<{
 
     const value = () => contents().value
     const name = () => (value().type === 'None' ? '*' : (value() as ExcludeFsNode).name)
+    const title = () => {
+        if (value().type === 'None') return undefined
+        const { entry, stats } = value() as ExcludeFsNode
+        if (value().type === 'Dir') {
+            return [
+                `Size: ${stats.size}`,
+                `Packed Size: ${stats.packedSize}`,
+                `Files: ${stats.files}`,
+                stats.ratio !== null && `Ratio: ${(stats.ratio * 100).toFixed(1)}%`,
+            ]
+                .filter(Boolean)
+                .join('\n')
+        }
+        return [
+            entry.size !== null && `Size: ${entry.size}`,
+            entry.packedSize !== null && `Packed Size: ${entry.packedSize}`,
+            entry.method && `Method: ${entry.method}`,
+            entry.encrypted && 'Encrypted',
+            entry.crc && `CRC: ${entry.crc}`,
+            entry.comment && `Comment: ${entry.comment}`,
+        ]
+            .filter(Boolean)
+            .join('\n')
+    }
 
     const emojiNameElement = () => {
         enum BeforeContent {
@@ -21,7 +45,7 @@ export const ArchiveContent: Component<{
         }
         const content = value().type === 'None' || value().type === 'Dir' ? BeforeContent.Dir : BeforeContent.File
         return (
-            <span class={`break-all before:(position-absolute left-0) ${content}`} classList={{ 'color-violet-400': contents().unziped }}>
+            <span class={`break-all before:(position-absolute left-0) ${content}`} classList={{ 'color-violet-400': contents().unziped }} title={title()}>
                 {name()}
             </span>
         )
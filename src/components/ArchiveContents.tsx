@@ -16,7 +16,9 @@ import {
     type Archive,
     type ArchiveContents as SuperArchiveContents,
     type Codepage,
+    type ExtractProgress,
     type FsNode,
+    type ListingProgressEvent,
     type ShowArchiveContentsEvent,
     type UnzipedArchiveEvent,
 } from '../bindings'
@@ -27,6 +29,7 @@ import { RefreshArchiveButton } from './RefreshArchiveButton'
 import { RemoveArchiveButton } from './RemoveArchiveButton'
 import { UnzipControl } from './UnzipControl'
 import { LoadingArchiveButton } from './LoadingArchiveButton'
+import { ExtractJobButtons } from './ExtractJobButtons'
 
 export type FileTree = {
     value: FsNode
@@ -43,7 +46,17 @@ type ArchiveContents = Omit<SuperArchiveContents, 'contents'> & {
 type ArchiveExtend = {
     count: FileCounter
     unzippingFile: string
-    unzipStatus: null | 'Running' | 'Completed'
+    unzipStatus: null | 'Queued' | 'Running' | 'Paused' | 'Cancelled' | 'Completed' | 'Failed'
+    // 解压失败的原因.
+    unzipError: string | null
+    // 解压任务的 ID, 用于暂停和取消.
+    jobId: number | null
+    // 解压的进度, 由后端定时发送.
+    progress: ExtractProgress | null
+    // 正在列出的条目数.
+    listedEntries: number
+    // 当前的 7-Zip 不支持的格式, 列出前就提示.
+    unsupportedFormat: string | null
 }
 
 export type FileStore = ArchiveContents & ArchiveExtend & { id: number }
@@ -56,6 +69,19 @@ function newArchiveContents(path: string): ArchiveContents {
         codepage: null,
         multiVolume: null,
         hasRootDir: false,
+        info: {
+            archiveType: null,
+            physicalSize: null,
+            headersSize: null,
+            method: null,
+            solid: false,
+            blocks: null,
+            encryptedHeaders: false,
+            comment: null,
+            recoveryRecord: false,
+            locked: false,
+        },
+        stats: { size: 0, packedSize: 0, files: 0, ratio: null },
     }
 }
 
@@ -63,6 +89,11 @@ const defaultArchiveExtend: ArchiveExtend = {
     count: createFileCount(),
     unzippingFile: '',
     unzipStatus: null,
+    unzipError: null,
+    jobId: null,
+    progress: null,
+    listedEntries: 0,
+    unsupportedFormat: null,
 }
 
 function newFileStore(path: string, id: number): FileStore {
@@ -105,9 +136,18 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
             })
         setFiles('files', [...files.files, ...path2Files])
         const unlisten = await events.showArchiveContentsEvent.listen(event => onDragDrop(event))
-        await commands.showArchivesContents(paths, password()).finally(() => unlisten())
+        const unlistenProgress = await events.listingProgressEvent.listen(event => onListingProgress(event))
+        await commands.showArchivesContents(paths, password(), null, null).finally(() => {
+            unlisten()
+            unlistenProgress()
+        })
     })
 
+    const onListingProgress = (event: Event<ListingProgressEvent>) => {
+        const { path, entries } = event.payload
+        setFiles('files', file => file.path === path, 'listedEntries', entries)
+    }
+
     const onDragDrop = (event: Event<ShowArchiveContentsEvent>) => {
         const result = event.payload
         console.log(result)
@@ -120,6 +160,10 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
                     'files',
                     files.files.filter(f => f.path !== error.UnsupportedFile),
                 )
+            } else if ('UnsupportedFormat' in error) {
+                const [path, format] = error.UnsupportedFormat
+                console.warn('UnsupportedFormat', path, format)
+                setFiles('files', file => file.path === path, 'unsupportedFormat', format)
             }
             return
         }
@@ -144,6 +188,10 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
                 file.contents = ac.contents
                 file.codepage = ac.codepage
                 file.hasRootDir = ac.hasRootDir
+                file.info = ac.info
+                file.stats = ac.stats
+                file.listedEntries = 0
+                file.unsupportedFormat = null
                 file.count = handleFileCount(ac.contents, ac.hasRootDir)
                 if (ac.multiVolume) {
                     file.path = ac.multiVolume.volumes[0] ?? path
@@ -197,6 +245,22 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
                 }
                 return
             }
+            if (unzipedArchiveStatus === 'Queued' || unzipedArchiveStatus === 'Paused' || unzipedArchiveStatus === 'Cancelled') {
+                setFiles('files', file => file.path === archivePath, 'unzipStatus', unzipedArchiveStatus)
+            }
+            return
+        }
+        if ('Job' in unzipedArchiveStatus) {
+            setFiles('files', file => file.path === archivePath, 'jobId', unzipedArchiveStatus.Job)
+            return
+        }
+        if ('Progress' in unzipedArchiveStatus) {
+            setFiles('files', file => file.path === archivePath, 'progress', unzipedArchiveStatus.Progress)
+            return
+        }
+        if ('Failed' in unzipedArchiveStatus) {
+            setFiles('files', file => file.path === archivePath, { unzipStatus: 'Failed', unzipError: unzipedArchiveStatus.Failed })
+            return
         }
 
         const parts = unzipedArchiveStatus.Ok.split(/[\\\/]/)
@@ -272,7 +336,11 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
             }),
         )
         const unlisten = await events.showArchiveContentsEvent.listen(event => onDragDrop(event))
-        await commands.refreshArchiveContents(file as Archive, password()).finally(() => unlisten())
+        const unlistenProgress = await events.listingProgressEvent.listen(event => onListingProgress(event))
+        await commands.refreshArchiveContents(file as Archive, password(), null, null).finally(() => {
+            unlisten()
+            unlistenProgress()
+        })
     }
 
     const handleSetCodepage = (path: string, codepage: Codepage | null) => {
@@ -309,6 +377,9 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
                                     <Separator />
                                     <span class="text-muted-foreground">{`📁${item.count.dir[1]}/${item.count.dir[0]}`}</span>
                                     <span class="text-muted-foreground">{`📄${item.count.file[1]}/${item.count.file[0]}`}</span>
+                                    <Show when={item.listedEntries > 0}>
+                                        <span class="text-muted-foreground">Listing: {item.listedEntries}</span>
+                                    </Show>
 
                                     <Show when={item.multiVolume}>
                                         {value => (
@@ -323,24 +394,65 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
                                         )}
                                     </Show>
                                 </Flex>
+                                <Show when={item.unsupportedFormat}>
+                                    {format => (
+                                        <Badge variant="destructive" class="flex-shrink-0" title="The installed 7-Zip can't open this format">
+                                            {format()} unsupported
+                                        </Badge>
+                                    )}
+                                </Show>
+                                <Show when={item.unzipStatus === 'Failed'}>
+                                    <Badge variant="destructive" class="flex-shrink-0" title={item.unzipError ?? undefined}>
+                                        Failed
+                                    </Badge>
+                                </Show>
                                 <Switch
                                     fallback={
                                         <RefreshArchiveButton
                                             onRefresh={() => refreshArchive(item.path)}
                                             class="flex-shrink-0"
-                                            disabled={item.unzipStatus === 'Running'}
+                                            disabled={item.unzipStatus === 'Running' || item.unzipStatus === 'Queued'}
                                         />
                                     }
                                 >
-                                    <Match when={item.unzipStatus === 'Running'}>
-                                        <LoadingArchiveButton type="long" class="flex-shrink-0" />
+                                    <Match when={item.unzipStatus === 'Queued'}>
+                                        <Badge variant="outline" class="flex-shrink-0">
+                                            Queued
+                                        </Badge>
+                                        <Show when={item.jobId}>
+                                            {jobId => <ExtractJobButtons jobId={jobId()} paused={false} queued class="flex-shrink-0" />}
+                                        </Show>
+                                    </Match>
+                                    <Match when={item.unzipStatus === 'Running' || item.unzipStatus === 'Paused'}>
+                                        <Show when={item.unzipStatus === 'Running'}>
+                                            <LoadingArchiveButton type="long" class="flex-shrink-0" />
+                                        </Show>
+                                        <Show when={item.progress}>
+                                            {progress => (
+                                                <Tooltip>
+                                                    <TooltipTrigger as="span" class="flex-shrink-0 text-xs tabular-nums">
+                                                        {progress().percent === null ? `${progress().done} B` : `${Math.floor(progress().percent ?? 0)}%`}
+                                                    </TooltipTrigger>
+                                                    <TooltipContent>
+                                                        <div>{`${Math.round(progress().throughput)} B/s`}</div>
+                                                        <Show when={progress().eta !== null}>
+                                                            <div>{`ETA: ${Math.ceil(progress().eta ?? 0)} s`}</div>
+                                                        </Show>
+                                                        <Show when={progress().file}>{file => <div>{file()}</div>}</Show>
+                                                    </TooltipContent>
+                                                </Tooltip>
+                                            )}
+                                        </Show>
+                                        <Show when={item.jobId}>
+                                            {jobId => <ExtractJobButtons jobId={jobId()} paused={item.unzipStatus === 'Paused'} class="flex-shrink-0" />}
+                                        </Show>
                                     </Match>
                                 </Switch>
 
                                 <RemoveArchiveButton
                                     onRemove={() => removeArchive(item.path)}
                                     class="flex-shrink-0"
-                                    disabled={files.files.some(f => f.unzipStatus === 'Running')}
+                                    disabled={files.files.some(f => f.unzipStatus === 'Running' || f.unzipStatus === 'Queued')}
                                 />
                             </AccordionTrigger>
                             <AccordionContent>
@@ -350,6 +462,13 @@ export const ArchiveContentsComponent: Component<ComponentProps<'div'>> = props
                                         setCodepage={(codepage: Codepage | null) => handleSetCodepage(item.path, codepage)}
                                         onRefresh={() => refreshArchive(item.path)}
                                     />
+                                    <Show when={item.info.archiveType}>
+                                        <Badge variant="outline" title={[item.info.method, item.info.comment].filter(Boolean).join('\n')}>
+                                            {item.info.archiveType}
+                                            {item.info.solid ? ' solid' : ''}
+                                            {item.info.encryptedHeaders ? ' 🔒' : ''}
+                                        </Badge>
+                                    </Show>
                                     <Show when={item.password}>
                                         <Badge class="">{item.password}</Badge>
                                     </Show>
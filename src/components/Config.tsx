@@ -43,7 +43,7 @@ export const AppConfigProvider: FlowComponent = props => {
         }
         return result.data
     })
-    const [configStore, setAppConfig] = createStore<AppConfig>({ target: { dir: '', canInput: false }, passwords: [], autoDelete: false })
+    const [configStore, setAppConfig] = createStore<AppConfig>({ target: { dir: '', canInput: false }, passwords: [], autoDelete: false, sevenzPath: null, sevenzMirror: null, maxConcurrency: null })
 
     const [, setTarget] = createStore(configStore.target)
     const [, setPasswords] = createStore(configStore.passwords)
@@ -92,6 +92,8 @@ export const AppConfigProvider: FlowComponent = props => {
                     config.setAutoDelete(value().autoDelete)
                     config.setPasswords(value().passwords)
                     config.setTarget(value().target)
+                    // 界面上不能修改, 保留配置文件中的值.
+                    setAppConfig('maxConcurrency', value().maxConcurrency)
                     setInited(true)
                     return <AppConfigContext.Provider value={config}>{props.children}</AppConfigContext.Provider>
                 }}
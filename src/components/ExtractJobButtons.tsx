@@ -0,0 +1,58 @@
+import { Show, splitProps, type Component, type ComponentProps, type JSX } from 'solid-js'
+
+import { commands } from '~/bindings'
+import { Button } from '~/components/ui/button'
+import { Tooltip, TooltipContent, TooltipTrigger } from '~/components/ui/tooltip'
+import { cn } from '~/lib/utils'
+
+interface ExtractJobButtonsProps extends ComponentProps<'div'> {
+    jobId: number
+    paused: boolean
+    /** Only cancelling applies while the extraction waits in the queue. */
+    queued?: boolean
+}
+
+export const ExtractJobButtons: Component<ExtractJobButtonsProps> = props => {
+    const [local, others] = splitProps(props, ['class', 'jobId', 'paused', 'queued'])
+
+    const handle =
+        (command: (id: number) => ReturnType<typeof commands.cancelJob>): JSX.EventHandler<Element, Event> =>
+        async event => {
+            event.stopPropagation()
+            const result = await command(local.jobId)
+            if (result.status === 'error') {
+                console.error(result.error)
+            }
+        }
+
+    return (
+        <div class={cn('flex', local.class)} {...others}>
+            <Show when={!local.queued}>
+                <Show
+                    when={local.paused}
+                    fallback={
+                        <Tooltip>
+                            <TooltipTrigger as={Button} onClick={handle(commands.pauseJob)} variant="ghost" size="sm" class="text-base px-2">
+                                <div class="i-material-symbols-light-pause-rounded" />
+                            </TooltipTrigger>
+                            <TooltipContent>Pause</TooltipContent>
+                        </Tooltip>
+                    }
+                >
+                    <Tooltip>
+                        <TooltipTrigger as={Button} onClick={handle(commands.resumeJob)} variant="ghost" size="sm" class="text-base px-2">
+                            <div class="i-material-symbols-light-play-arrow-rounded" />
+                        </TooltipTrigger>
+                        <TooltipContent>Resume</TooltipContent>
+                    </Tooltip>
+                </Show>
+            </Show>
+            <Tooltip>
+                <TooltipTrigger as={Button} onClick={handle(commands.cancelJob)} variant="ghost" size="sm" class="text-base px-2">
+                    <div class="i-material-symbols-light-close-rounded" />
+                </TooltipTrigger>
+                <TooltipContent>Cancel</TooltipContent>
+            </Tooltip>
+        </div>
+    )
+}
@@ -1,5 +1,6 @@
-import { createResource, Match, Show, Switch, type Component } from 'solid-js'
-import { commands } from '~/bindings'
+import { Channel } from '@tauri-apps/api/core'
+import { createResource, createSignal, Match, Show, Switch, type Component } from 'solid-js'
+import { commands, type InstallProgress, type UpdateCheck } from '~/bindings'
 import { Button } from '~/components/ui/button'
 
 export const SevenZipVersion: Component = () => {
@@ -12,14 +13,58 @@ export const SevenZipVersion: Component = () => {
         return version.data
     })
 
+    const [progress, setProgress] = createSignal<InstallProgress | null>(null)
+
     async function downloadSevenZip() {
         console.log('downloadSevenZip')
-        const result = await commands.download7z()
+        const onProgress = new Channel<InstallProgress>()
+        onProgress.onmessage = setProgress
+        const result = await commands.download7z(null, onProgress)
         console.log(result)
+        setProgress(null)
         refetchVerison()
     }
 
-    const versionShort = () => version()?.split(': C')[0]
+    const [update, setUpdate] = createSignal<UpdateCheck | null>(null)
+
+    async function checkUpdate() {
+        const result = await commands.check7zUpdate()
+        if (result.status === 'error') {
+            console.error(result.error)
+            return
+        }
+        setUpdate(result.data)
+    }
+
+    async function upgradeSevenZip() {
+        const onProgress = new Channel<InstallProgress>()
+        onProgress.onmessage = setProgress
+        const result = await commands.upgrade7z(onProgress)
+        console.log(result)
+        setProgress(null)
+        setUpdate(null)
+        refetchVerison()
+    }
+
+    const progressText = () => {
+        const p = progress()
+        if (!p) return ''
+        return p.total ? `${p.phase} ${Math.floor((p.bytes / p.total) * 100)}%` : p.phase
+    }
+
+    const versionShort = () => {
+        const info = version()
+        if (!info) return undefined
+        const flavor = info.flavor === 'P7zip' ? 'p7zip' : '7-Zip'
+        return info.architecture ? `${flavor} ${info.version} (${info.architecture})` : `${flavor} ${info.version}`
+    }
+    const versionTitle = () => {
+        const info = version()
+        if (!info) return undefined
+        return [info.buildDate, `Formats: ${info.formats.join(', ')}`, `Codecs: ${info.codecs.join(', ')}`]
+            .filter(Boolean)
+            .join('\n')
+    }
 
     return (
         <>
@@ -28,12 +73,38 @@ export const SevenZipVersion: Component = () => {
             </Show>
             <Switch>
                 <Match when={version.error}>
-                    <span>
-                        7z not found.<Button onClick={downloadSevenZip}>Download</Button>
-                    </span>
+                    <Show
+                        when={progress()}
+                        fallback={
+                            <span>
+                                7z not found.<Button onClick={downloadSevenZip}>Download</Button>
+                            </span>
+                        }
+                    >
+                        <span>
+                            {progressText()}
+                            <Button onClick={() => commands.cancelDownload7z()}>Cancel</Button>
+                        </span>
+                    </Show>
                 </Match>
                 <Match when={version()}>
-                    <span>{versionShort()}</span>
+                    <span title={versionTitle()}>{versionShort()}</span>
+                    <Switch fallback={<Button onClick={checkUpdate}>Check update</Button>}>
+                        <Match when={progress()}>
+                            <span>{progressText()}</span>
+                        </Match>
+                        <Match when={update()?.updateAvailable}>
+                            <span>
+                                {update()?.latest}
+                                <Show
+                                    when={update()?.installable}
+                                    fallback={<span title="No verified release to install yet"> (not verified)</span>}
+                                >
+                                    <Button onClick={upgradeSevenZip}>Upgrade to {update()?.verified}</Button>
+                                </Show>
+                            </span>
+                        </Match>
+                    </Switch>
                 </Match>
             </Switch>
         </>